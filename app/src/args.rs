@@ -1,4 +1,8 @@
-use std::path::PathBuf;
+use std::{
+    fs::File,
+    io::BufReader,
+    path::PathBuf,
+};
 
 use anyhow::Result;
 use clap::{
@@ -7,6 +11,14 @@ use clap::{
     Command,
     Parser,
 };
+use lb_importer_services::service::{
+    generic_csv,
+    generic_json::{
+        self,
+        JsonPath,
+    },
+    TimestampFormat,
+};
 use time::{
     format_description::{
         well_known::Rfc3339,
@@ -40,46 +52,289 @@ pub(crate) struct Args {
     #[arg(short, long, value_parser = parse_datetime)]
     pub after: Option<OffsetDateTime>,
 
+    /// Minimum play time in seconds for a track to be imported, for services that record it
+    #[arg(long, default_value_t = 30)]
+    pub min_play_time: u16,
+
+    /// UTC offset to use for services that record timestamps in local time [default: offset of this system]
+    #[arg(long, value_parser = parse_offset, allow_hyphen_values = true)]
+    pub utc_offset: Option<UtcOffset>,
+
     /// How many listens to import per request
     #[arg(long, default_value_t = 1000)]
     pub batch_size: usize,
 
-    /// The service where the dump came from
+    /// The service where the dump came from, detected separately for each file when not given
     #[command(flatten)]
-    pub service: Service,
+    pub service: Option<Service>,
 
-    /// One or more json files containing play history
+    /// One or more files containing play history. Use `-` to read from stdin
+    ///
+    /// Directories are searched recursively for files with names matching those exported by the selected service,
+    /// or by any service with distinctive file names when none is selected
     #[arg(required = true)]
     pub files: Vec<PathBuf>,
 }
 
+/// Names of the files exported by each service, as regexes matched against the end of the file path
+const SPOTIFY_FILES: &str = r"Streaming_History_Audio_[\d-]+(_\d+)?.json | endsong_\d+.json | StreamingHistory(_music_|_podcast_)?\d+.json";
+const LISTENBRAINZ_FILES: &str = r"\w+_lb-\d{4}-\d{2}-\d{2}.json | listenbrainz_[\w-]+.zip | listens/\d+/\d+.jsonl";
+const LASTFM_FILES: &str = r"[\w-]+.csv";
+const LASTFM_API_FILES: &str = r"[\w-]+.json";
+const APPLE_MUSIC_FILES: &str = r"Apple Music Play Activity.csv";
+const YOUTUBE_MUSIC_FILES: &str = r"watch-history.json";
+const SCROBBLER_LOG_FILES: &str = r".scrobbler.log";
+const MALOJA_FILES: &str = r"maloja_export_\d+.json";
+const DEEZER_FILES: &str = r"[\w-]+.xlsx";
+const JELLYFIN_FILES: &str = r"playback_reporting.db";
+const PLEX_FILES: &str = r"com.plexapp.plugins.library.db";
+const MPDSCRIBBLE_FILES: &str = r"[\w.-]+.journal";
+const CSV_FILES: &str = r"[\w-]+.csv";
+const JSON_FILES: &str = r"[\w-]+.json";
+const TRAKTOR_FILES: &str = r"history_\d{4}y\d{2}m\d{2}d_\d{2}h\d{2}m\d{2}s.nml";
+const ROCKBOX_FILES: &str = r"playback.log";
+const MY_ACTIVITY_FILES: &str = r"My Activity.html";
+const AMAZON_MUSIC_FILES: &str = r"[\w.-]+.csv";
+
 #[derive(Debug)]
 pub(crate) enum Service {
     Spotify(SpotifyArgs),
     ListenBrainz,
+    LastFm,
+    LastFmApi,
+    AppleMusic,
+    YouTubeMusic,
+    ScrobblerLog,
+    Maloja,
+    Deezer,
+    Jellyfin(JellyfinArgs),
+    Plex(PlexArgs),
+    Mpdscribble,
+    Csv(Box<CsvArgs>),
+    Json(Box<JsonArgs>),
+    Traktor,
+    Rockbox(RockboxArgs),
+    MyActivity,
+    AmazonMusic,
+}
+
+impl Service {
+    /// Name of the flag used to select this service
+    pub fn name(&self) -> &'static str {
+        use Service::*;
+        match self {
+            Spotify(_) => "spotify",
+            ListenBrainz => "listenbrainz",
+            LastFm => "lastfm",
+            LastFmApi => "lastfm-api",
+            AppleMusic => "apple-music",
+            YouTubeMusic => "youtube-music",
+            ScrobblerLog => "scrobbler-log",
+            Maloja => "maloja",
+            Deezer => "deezer",
+            Jellyfin(_) => "jellyfin",
+            Plex(_) => "plex",
+            Mpdscribble => "mpdscribble",
+            Csv(_) => "csv",
+            Json(_) => "json",
+            Traktor => "traktor",
+            Rockbox(_) => "rockbox",
+            MyActivity => "my-activity",
+            AmazonMusic => "amazon-music",
+        }
+    }
+
+    /// Regex matching the names of the files this service exports
+    pub fn file_pattern(&self) -> &'static str {
+        use Service::*;
+        match self {
+            Spotify(_) => SPOTIFY_FILES,
+            ListenBrainz => LISTENBRAINZ_FILES,
+            LastFm => LASTFM_FILES,
+            LastFmApi => LASTFM_API_FILES,
+            AppleMusic => APPLE_MUSIC_FILES,
+            YouTubeMusic => YOUTUBE_MUSIC_FILES,
+            ScrobblerLog => SCROBBLER_LOG_FILES,
+            Maloja => MALOJA_FILES,
+            Deezer => DEEZER_FILES,
+            Jellyfin(_) => JELLYFIN_FILES,
+            Plex(_) => PLEX_FILES,
+            Mpdscribble => MPDSCRIBBLE_FILES,
+            Csv(_) => CSV_FILES,
+            Json(_) => JSON_FILES,
+            Traktor => TRAKTOR_FILES,
+            Rockbox(_) => ROCKBOX_FILES,
+            MyActivity => MY_ACTIVITY_FILES,
+            AmazonMusic => AMAZON_MUSIC_FILES,
+        }
+    }
 }
 
 impl clap::Args for Service {
     fn augment_args(cmd: clap::Command) -> clap::Command {
         const HEADING: &str = "Services";
-        cmd.group(ArgGroup::new("service").args(["spotify", "listenbrainz"]).required(true))
+        cmd.group(ArgGroup::new("service"))
             .arg(
                 arg!(--spotify)
+                    .group("service")
                     .help_heading(HEADING)
                     .help("Import files from a spotify dump")
-                    .long_help(r"endsong_\d+.json | StreamingHistory\d+.json"),
+                    .long_help(SPOTIFY_FILES),
+            )
+            .args(
+                SpotifyArgs::augment_args(Command::new(""))
+                    .get_arguments()
+                    .cloned()
+                    .map(|a| a.requires("spotify").help_heading("Spotify Options")),
             )
             .arg(
                 arg!(--listenbrainz)
+                    .group("service")
                     .help_heading(HEADING)
                     .help("Import files from a listenbrainz dump")
-                    .long_help(r"\w+_lb-\d{4}-\d{2}-\d{2}.json"),
+                    .long_help(LISTENBRAINZ_FILES),
+            )
+            .arg(
+                arg!(--lastfm)
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import files from a last.fm csv export")
+                    .long_help(LASTFM_FILES),
+            )
+            .arg(
+                arg!(--"lastfm-api")
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import saved last.fm user.getRecentTracks response pages")
+                    .long_help(LASTFM_API_FILES),
+            )
+            .arg(
+                arg!(--"apple-music")
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import files from an apple music privacy export")
+                    .long_help(APPLE_MUSIC_FILES),
+            )
+            .arg(
+                arg!(--"youtube-music")
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import youtube music entries from a google takeout watch history")
+                    .long_help(YOUTUBE_MUSIC_FILES),
+            )
+            .arg(
+                arg!(--"scrobbler-log")
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import an audioscrobbler log from rockbox or another portable player")
+                    .long_help(SCROBBLER_LOG_FILES),
+            )
+            .arg(
+                arg!(--maloja)
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import files from a maloja backup")
+                    .long_help(MALOJA_FILES),
+            )
+            .arg(
+                arg!(--deezer)
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import the listening history from a deezer export")
+                    .long_help(DEEZER_FILES),
+            )
+            .arg(
+                arg!(--jellyfin)
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import audio plays from a jellyfin playback reporting database")
+                    .long_help(JELLYFIN_FILES),
             )
             .args(
-                SpotifyArgs::augment_args(Command::new(""))
+                JellyfinArgs::augment_args(Command::new(""))
                     .get_arguments()
                     .cloned()
-                    .map(|sa| sa.requires("spotify").help_heading("Spotify Options")),
+                    .map(|a| a.requires("jellyfin").help_heading("Jellyfin Options")),
+            )
+            .arg(
+                arg!(--plex)
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import track views from a plex media server library database")
+                    .long_help(PLEX_FILES),
+            )
+            .args(
+                PlexArgs::augment_args(Command::new(""))
+                    .get_arguments()
+                    .cloned()
+                    .map(|a| a.requires("plex").help_heading("Plex Options")),
+            )
+            .arg(
+                arg!(--mpdscribble)
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import unsent scrobbles from an mpdscribble journal")
+                    .long_help(MPDSCRIBBLE_FILES),
+            )
+            .arg(
+                arg!(--csv)
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import a csv file using a custom column mapping")
+                    .long_help(CSV_FILES),
+            )
+            .args(
+                CsvArgs::augment_args(Command::new(""))
+                    .get_arguments()
+                    .cloned()
+                    .map(|a| a.requires("csv").help_heading("CSV Options")),
+            )
+            .arg(
+                arg!(--json)
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import a json file using custom path expressions")
+                    .long_help(JSON_FILES),
+            )
+            .args(
+                JsonArgs::augment_args(Command::new(""))
+                    .get_arguments()
+                    .cloned()
+                    .map(|a| a.requires("json").help_heading("JSON Options")),
+            )
+            .arg(
+                arg!(--traktor)
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import the history playlists from traktor")
+                    .long_help(TRAKTOR_FILES),
+            )
+            .arg(
+                arg!(--rockbox)
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import a rockbox playback log, reading tags from a local copy of the music")
+                    .long_help(ROCKBOX_FILES),
+            )
+            .args(
+                RockboxArgs::augment_args(Command::new(""))
+                    .get_arguments()
+                    .cloned()
+                    .map(|a| a.requires("rockbox").help_heading("Rockbox Options")),
+            )
+            .arg(
+                arg!(--"my-activity")
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import google play music and youtube music entries from a google takeout activity page")
+                    .long_help(MY_ACTIVITY_FILES),
+            )
+            .arg(
+                arg!(--"amazon-music")
+                    .group("service")
+                    .help_heading(HEADING)
+                    .help("Import the listening history from an amazon music data request")
+                    .long_help(AMAZON_MUSIC_FILES),
             )
     }
 
@@ -91,9 +346,41 @@ impl clap::Args for Service {
 impl clap::FromArgMatches for Service {
     fn from_arg_matches(matches: &clap::ArgMatches) -> std::result::Result<Self, clap::Error> {
         if matches.get_flag("spotify") {
-            Ok(Service::Spotify(SpotifyArgs::from_arg_matches(matches)?))
+            Ok(Self::Spotify(SpotifyArgs::from_arg_matches(matches)?))
         } else if matches.get_flag("listenbrainz") {
             Ok(Self::ListenBrainz)
+        } else if matches.get_flag("lastfm") {
+            Ok(Self::LastFm)
+        } else if matches.get_flag("lastfm-api") {
+            Ok(Self::LastFmApi)
+        } else if matches.get_flag("apple-music") {
+            Ok(Self::AppleMusic)
+        } else if matches.get_flag("youtube-music") {
+            Ok(Self::YouTubeMusic)
+        } else if matches.get_flag("scrobbler-log") {
+            Ok(Self::ScrobblerLog)
+        } else if matches.get_flag("maloja") {
+            Ok(Self::Maloja)
+        } else if matches.get_flag("deezer") {
+            Ok(Self::Deezer)
+        } else if matches.get_flag("jellyfin") {
+            Ok(Self::Jellyfin(JellyfinArgs::from_arg_matches(matches)?))
+        } else if matches.get_flag("plex") {
+            Ok(Self::Plex(PlexArgs::from_arg_matches(matches)?))
+        } else if matches.get_flag("mpdscribble") {
+            Ok(Self::Mpdscribble)
+        } else if matches.get_flag("csv") {
+            Ok(Self::Csv(Box::new(CsvArgs::from_arg_matches(matches)?)))
+        } else if matches.get_flag("json") {
+            Ok(Self::Json(Box::new(JsonArgs::from_arg_matches(matches)?)))
+        } else if matches.get_flag("traktor") {
+            Ok(Self::Traktor)
+        } else if matches.get_flag("rockbox") {
+            Ok(Self::Rockbox(RockboxArgs::from_arg_matches(matches)?))
+        } else if matches.get_flag("my-activity") {
+            Ok(Self::MyActivity)
+        } else if matches.get_flag("amazon-music") {
+            Ok(Self::AmazonMusic)
         } else {
             Err(clap::Error::new(clap::error::ErrorKind::MissingRequiredArgument))
         }
@@ -102,16 +389,205 @@ impl clap::FromArgMatches for Service {
     fn update_from_arg_matches(&mut self, matches: &clap::ArgMatches) -> std::result::Result<(), clap::Error> {
         match self {
             Service::Spotify(ref mut a) => a.update_from_arg_matches(matches),
-            Service::ListenBrainz => Ok(()),
+            Service::Jellyfin(ref mut a) => a.update_from_arg_matches(matches),
+            Service::Plex(ref mut a) => a.update_from_arg_matches(matches),
+            Service::Csv(ref mut a) => a.update_from_arg_matches(matches),
+            Service::Json(ref mut a) => a.update_from_arg_matches(matches),
+            Service::Rockbox(ref mut a) => a.update_from_arg_matches(matches),
+            _ => Ok(()),
         }
     }
 }
 
 #[derive(clap::Args, Debug)]
 pub(crate) struct SpotifyArgs {
-    /// Minimum play time in seconds for a track to be imported
-    #[arg(long, default_value_t = 30)]
-    pub min_play_time: u16,
+    /// Also import podcast episodes, using the show as the artist
+    #[arg(long)]
+    pub include_podcasts: bool,
+
+    /// How audiobook chapters should be imported
+    ///
+    /// Spotify doesn't record the author, so audiobooks are imported with an artist of `[unknown]` and the book as
+    /// the release
+    #[arg(long, value_enum, default_value_t = AudiobookPolicy::Skip)]
+    pub audiobooks: AudiobookPolicy,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AudiobookPolicy {
+    /// Don't import audiobooks
+    Skip,
+    /// Import each chapter as a separate listen
+    Chapters,
+    /// Import a single listen of the book for each listening session, at the first chapter played
+    Books,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct JellyfinArgs {
+    /// Only import plays from this jellyfin user id
+    #[arg(long = "jellyfin-user")]
+    pub user: Option<Uuid>,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct PlexArgs {
+    /// Only import views from this plex account id. The server owner is usually `1`
+    #[arg(long = "plex-account")]
+    pub account: Option<i64>,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CsvArgs {
+    /// Json file containing the column mapping, using the option names below without the `csv-` prefix.
+    /// Any options given on the command line override the values from this file
+    #[arg(id = "csv-mapping", long = "csv-mapping", value_name = "FILE")]
+    pub mapping: Option<PathBuf>,
+
+    /// Column containing the track name
+    #[arg(id = "csv-track", long = "csv-track", value_name = "COLUMN")]
+    pub track: Option<String>,
+
+    /// Column containing the artist name
+    #[arg(id = "csv-artist", long = "csv-artist", value_name = "COLUMN")]
+    pub artist: Option<String>,
+
+    /// Column containing the release name
+    #[arg(id = "csv-release", long = "csv-release", value_name = "COLUMN")]
+    pub release: Option<String>,
+
+    /// Column containing the time of the listen
+    #[arg(id = "csv-timestamp", long = "csv-timestamp", value_name = "COLUMN")]
+    pub timestamp: Option<String>,
+
+    /// Format of the timestamp column. One of `unix`, `unix-ms`, `rfc3339`, or a format description such as
+    /// `[year]-[month]-[day] [hour]:[minute]:[second]`. Times without an offset use `--utc-offset` [default: unix]
+    #[arg(id = "csv-timestamp-format", long = "csv-timestamp-format", value_name = "FORMAT")]
+    pub timestamp_format: Option<TimestampFormat>,
+
+    /// Column containing the recording mbid
+    #[arg(id = "csv-recording-mbid", long = "csv-recording-mbid", value_name = "COLUMN")]
+    pub recording_mbid: Option<String>,
+
+    /// Column containing the release mbid
+    #[arg(id = "csv-release-mbid", long = "csv-release-mbid", value_name = "COLUMN")]
+    pub release_mbid: Option<String>,
+
+    /// Column containing artist mbids, separated by `,` or `;`
+    #[arg(id = "csv-artist-mbids", long = "csv-artist-mbids", value_name = "COLUMN")]
+    pub artist_mbids: Option<String>,
+
+    /// Field delimiter [default: ,]
+    #[arg(id = "csv-delimiter", long = "csv-delimiter", value_name = "DELIMITER")]
+    pub delimiter: Option<char>,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct RockboxArgs {
+    /// Directory containing the music files played on the device, used to read their tags
+    #[arg(
+        id = "rockbox-library",
+        long = "rockbox-library",
+        value_name = "DIR",
+        required = false,
+        required_if_eq("rockbox", "true")
+    )]
+    pub library: PathBuf,
+
+    /// Minimum fraction of a track that must have been played for it to be imported
+    #[arg(id = "rockbox-min-ratio", long = "rockbox-min-ratio", value_name = "RATIO", default_value_t = 0.5)]
+    pub min_ratio: f64,
+}
+
+/// Load the mapping file if one was given, then override it with any mapping options
+macro_rules! mapping {
+    ($args:ident, $mapping:ty, $($field:ident),+) => {{
+        let mut mapping = match $args.mapping {
+            Some(ref path) => <$mapping>::from_reader(File::open(path).map(BufReader::new)?)?,
+            None => <$mapping>::default(),
+        };
+        $(
+            if let Some(ref v) = $args.$field {
+                mapping.$field = v.clone().into();
+            }
+        )+
+        mapping
+    }};
+}
+
+impl CsvArgs {
+    pub fn mapping(&self) -> Result<generic_csv::Mapping> {
+        Ok(mapping!(
+            self,
+            generic_csv::Mapping,
+            track,
+            artist,
+            release,
+            timestamp,
+            timestamp_format,
+            recording_mbid,
+            release_mbid,
+            artist_mbids,
+            delimiter
+        ))
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct JsonArgs {
+    /// Json file containing the path mapping, using the option names below without the `json-` prefix.
+    /// Any options given on the command line override the values from this file
+    #[arg(id = "json-mapping", long = "json-mapping", value_name = "FILE")]
+    pub mapping: Option<PathBuf>,
+
+    /// Path to the track name, such as `items[].track.name`. The first `[]` selects the records to import
+    #[arg(id = "json-track", long = "json-track", value_name = "PATH")]
+    pub track: Option<JsonPath>,
+
+    /// Path to the artist name, such as `items[].track.artists[0].name`
+    #[arg(id = "json-artist", long = "json-artist", value_name = "PATH")]
+    pub artist: Option<JsonPath>,
+
+    /// Path to the release name
+    #[arg(id = "json-release", long = "json-release", value_name = "PATH")]
+    pub release: Option<JsonPath>,
+
+    /// Path to the time of the listen
+    #[arg(id = "json-timestamp", long = "json-timestamp", value_name = "PATH")]
+    pub timestamp: Option<JsonPath>,
+
+    /// Format of the timestamp, using the same values as `--csv-timestamp-format` [default: unix]
+    #[arg(id = "json-timestamp-format", long = "json-timestamp-format", value_name = "FORMAT")]
+    pub timestamp_format: Option<TimestampFormat>,
+
+    /// Path to the recording mbid
+    #[arg(id = "json-recording-mbid", long = "json-recording-mbid", value_name = "PATH")]
+    pub recording_mbid: Option<JsonPath>,
+
+    /// Path to the release mbid
+    #[arg(id = "json-release-mbid", long = "json-release-mbid", value_name = "PATH")]
+    pub release_mbid: Option<JsonPath>,
+
+    /// Path to one or more artist mbids, such as `items[].track.artists[].mbid`
+    #[arg(id = "json-artist-mbids", long = "json-artist-mbids", value_name = "PATH")]
+    pub artist_mbids: Option<JsonPath>,
+}
+
+impl JsonArgs {
+    pub fn mapping(&self) -> Result<generic_json::Mapping> {
+        Ok(mapping!(
+            self,
+            generic_json::Mapping,
+            track,
+            artist,
+            release,
+            timestamp,
+            timestamp_format,
+            recording_mbid,
+            release_mbid,
+            artist_mbids
+        ))
+    }
 }
 
 
@@ -138,3 +614,19 @@ fn parse_datetime(dt: &str) -> Result<OffsetDateTime> {
             .ok_or(e.into())
     })
 }
+
+fn parse_offset(offset: &str) -> Result<UtcOffset> {
+    const FMTS: &[&[FormatItem]] = &[
+        format_description!("[offset_hour]:[offset_minute]"),
+        format_description!("[offset_hour][offset_minute]"),
+        format_description!("[offset_hour padding:none]"),
+    ];
+
+    FMTS.iter()
+        .find_map(|fmt| UtcOffset::parse(offset, fmt).ok())
+        .ok_or_else(|| anyhow::anyhow!("expected an offset like `+02:00` or `-0500`"))
+}
+
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,71 @@
+use clap::FromArgMatches;
+
+use super::*;
+
+/// Option names of `A` with `prefix` removed, which are the keys of its mapping file
+fn mapping_keys<A: clap::Args>(prefix: &str) -> Vec<String> {
+    A::augment_args(Command::new(""))
+        .get_arguments()
+        .filter_map(|a| a.get_id().as_str().strip_prefix(prefix).map(str::to_owned))
+        .filter(|k| k != "mapping")
+        .collect()
+}
+
+/// Parse `A` from only a `--{prefix}mapping` file called `name` containing `contents`, then build its mapping with `map`
+fn from_mapping_file<A: clap::Args + FromArgMatches, M>(prefix: &str, name: &str, contents: &str, map: impl FnOnce(A) -> Result<M>) -> Result<M> {
+    let path = std::env::temp_dir().join(format!("lb-importer-{name}-{}.json", std::process::id()));
+    std::fs::write(&path, contents).expect("Failed to write mapping");
+    let matches = A::augment_args(Command::new("")).get_matches_from(["", &format!("--{prefix}mapping"), path.to_str().unwrap()]);
+    let mapping = map(A::from_arg_matches(&matches).expect("Failed to parse args"));
+    std::fs::remove_file(path).expect("Failed to clean up");
+    mapping
+}
+
+/// A mapping file with every one of `keys`, using `value` to get a valid value for each
+fn full_mapping(keys: &[String], value: impl Fn(&str) -> String) -> String {
+    let keys: serde_json::Map<_, _> = keys.iter().map(|k| (k.clone(), value(k).into())).collect();
+    serde_json::Value::Object(keys).to_string()
+}
+
+#[test]
+fn test_csv_mapping_keys() {
+    let keys = mapping_keys::<CsvArgs>("csv-");
+    let contents = full_mapping(&keys, |k| match k {
+        "timestamp-format" => "unix-ms".to_owned(),
+        "delimiter" => ";".to_owned(),
+        _ => k.to_owned(),
+    });
+    let mapping = from_mapping_file("csv-", "csv-keys", &contents, |a: CsvArgs| a.mapping()).expect("Failed to read mapping");
+    assert_eq!(mapping.artist_mbids.as_deref(), Some("artist-mbids"));
+    assert_eq!(mapping.delimiter, Some(';'));
+}
+
+#[test]
+fn test_json_mapping_keys() {
+    let keys = mapping_keys::<JsonArgs>("json-");
+    let contents = full_mapping(&keys, |k| match k {
+        "timestamp-format" => "unix-ms".to_owned(),
+        _ => format!("items[].{k}"),
+    });
+    let mapping = from_mapping_file("json-", "json-keys", &contents, |a: JsonArgs| a.mapping()).expect("Failed to read mapping");
+    assert_eq!(mapping.artist_mbids.map(|p| p.to_string()).as_deref(), Some("items[].artist-mbids"));
+}
+
+#[test]
+fn test_mapping_snake_case_keys() {
+    let csv = from_mapping_file("csv-", "csv-snake-case", r#"{"timestamp_format": "unix"}"#, |a: CsvArgs| a.mapping());
+    assert!(csv.is_err_and(|e| e.to_string() == "Invalid csv mapping"));
+    let json = from_mapping_file("json-", "json-snake-case", r#"{"recording_mbid": "[].mbid"}"#, |a: JsonArgs| a.mapping());
+    assert!(json.is_err_and(|e| e.to_string() == "Invalid json mapping"));
+}
+
+#[test]
+fn test_mapping_overridden() {
+    let mapping = from_mapping_file("csv-", "csv-override", r#"{"track": "Title", "artist": "Artist"}"#, |mut a: CsvArgs| {
+        a.track = Some("Name".to_owned());
+        a.mapping()
+    })
+    .expect("Failed to read mapping");
+    assert_eq!(mapping.track, "Name");
+    assert_eq!(mapping.artist, "Artist");
+}
@@ -0,0 +1,169 @@
+use std::{
+    fmt,
+    path::Path,
+};
+
+use anyhow::{
+    bail,
+    Result,
+};
+use regex::Regex;
+
+use crate::{
+    args::{
+        AudiobookPolicy,
+        JellyfinArgs,
+        PlexArgs,
+        Service,
+        SpotifyArgs,
+    },
+    input::{
+        self,
+        Found,
+    },
+    print_err,
+};
+
+/// A service that can be detected from its files
+struct Source {
+    service: Service,
+    files: Regex,
+    /// Whether the file names are specific enough to identify this service without looking at the contents
+    named: bool,
+    /// Text found near the start of files exported by this service. Any single marker is enough to match
+    markers: &'static [&'static [u8]],
+}
+
+/// Services that can be imported without any extra options. The generic csv and json importers need a mapping and
+/// would match almost anything, and rockbox needs the music library, so those always have to be selected explicitly.
+/// Deezer exports are xlsx files, which are compressed so nothing identifies them near the start of the file
+fn sources() -> Result<Vec<Source>> {
+    let sources: [(Service, bool, &[&[u8]]); 14] = [
+        (
+            Service::Spotify(SpotifyArgs {
+                include_podcasts: false,
+                audiobooks: AudiobookPolicy::Skip,
+            }),
+            true,
+            &[br#""ms_played""#, br#""msPlayed""#],
+        ),
+        (Service::ListenBrainz, true, &[br#""track_metadata""#]),
+        (Service::LastFm, false, &[b"uts,utc_time,artist"]),
+        (Service::LastFmApi, false, &[br#""recenttracks""#, br##""#text""##]),
+        (Service::AppleMusic, true, &[b"Play Duration Milliseconds"]),
+        (Service::YouTubeMusic, true, &[br#""titleUrl""#]),
+        (Service::ScrobblerLog, true, &[b"#AUDIOSCROBBLER/"]),
+        (Service::Maloja, true, &[br#""maloja""#]),
+        (Service::Jellyfin(JellyfinArgs { user: None }), true, &[b"PlaybackActivity"]),
+        (Service::Plex(PlexArgs { account: None }), true, &[b"metadata_item_views"]),
+        (Service::Mpdscribble, false, &[b"\nt = "]),
+        (Service::Traktor, true, &[b"<NML"]),
+        (Service::MyActivity, true, &[b"outer-cell"]),
+        (Service::AmazonMusic, false, &[b"consumptionDurationMs"]),
+    ];
+    sources
+        .into_iter()
+        .map(|(service, named, markers)| {
+            Ok(Source {
+                files: input::file_regex(service.file_pattern())?,
+                service,
+                named,
+                markers,
+            })
+        })
+        .collect()
+}
+
+/// Regex matching the names of the files exported by any detectable service with specific file names.
+/// Files only recognizable by their contents have to be given directly rather than found in a directory
+pub(crate) fn file_regex() -> Result<Regex> {
+    let patterns: Vec<_> = sources()?.iter().filter(|s| s.named).map(|s| s.service.file_pattern()).collect();
+    input::file_regex(&patterns.join(" | "))
+}
+
+/// Why a file couldn't be matched to a single service
+#[derive(Debug, PartialEq, Eq)]
+enum Undetected {
+    Unknown,
+    /// Names of all the services the file could be from
+    Ambiguous(String),
+}
+
+impl fmt::Display for Undetected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "could not detect the service"),
+            Self::Ambiguous(names) => write!(f, "could be from any of: {names}"),
+        }
+    }
+}
+
+/// Detect the service each of `files` came from, by its contents and file name, and group them by service.
+/// Files that don't match any service are reported and skipped, but files that could be from more than one fail the
+/// whole import before anything is imported. Only the start of each file is read, so most files are closed again
+/// until they're imported
+pub(crate) fn group(files: impl Iterator<Item = Found>) -> Result<Vec<(Service, Vec<Found>)>> {
+    let sources = sources()?;
+    let mut groups: Vec<Vec<Found>> = sources.iter().map(|_| Vec::new()).collect();
+    let mut ambiguous = false;
+    for file in files {
+        let head = match file.peek() {
+            Ok(head) => head,
+            Err(e) => {
+                print_err(&e);
+                continue;
+            },
+        };
+        match detect(&sources, &file.path, &head) {
+            Ok(i) => {
+                println!("Detected {} history in '{}'", sources[i].service.name(), file.path.display());
+                groups[i].push(file);
+            },
+            Err(reason @ Undetected::Unknown) => println!("Ignoring file '{}', {reason}", file.path.display()),
+            Err(reason @ Undetected::Ambiguous(_)) => {
+                eprintln!("File '{}' {reason}", file.path.display());
+                ambiguous = true;
+            },
+        }
+    }
+    if ambiguous {
+        bail!("Could not choose a service for some files, select one with a service flag or import them separately");
+    }
+    if groups.iter().all(Vec::is_empty) {
+        bail!("Could not detect the service for any files, select one with a service flag");
+    }
+
+    Ok(sources
+        .into_iter()
+        .zip(groups)
+        .filter(|(_, files)| !files.is_empty())
+        .map(|(s, files)| (s.service, files))
+        .collect())
+}
+
+/// Index of the single source matching `head`, using the file name of `path` to choose between multiple matches.
+/// Files without any markers can only be detected by name for services with specific file names
+fn detect(sources: &[Source], path: &Path, head: &[u8]) -> Result<usize, Undetected> {
+    let by_content: Vec<_> = (0..sources.len())
+        .filter(|&i| sources[i].markers.iter().any(|m| head.windows(m.len()).any(|w| w == *m)))
+        .collect();
+    let (candidates, by_marker) = match by_content.as_slice() {
+        [i] => return Ok(*i),
+        [] => ((0..sources.len()).filter(|&i| sources[i].named).collect(), false),
+        _ => (by_content, true),
+    };
+
+    let by_name: Vec<_> = candidates.iter().copied().filter(|&i| input::is_match(&sources[i].files, path)).collect();
+    match (by_name.as_slice(), by_marker) {
+        ([i], _) => Ok(*i),
+        ([], false) => Err(Undetected::Unknown),
+        ([], true) => Err(Undetected::Ambiguous(names(sources, &candidates))),
+        (_, _) => Err(Undetected::Ambiguous(names(sources, &by_name))),
+    }
+}
+
+fn names(sources: &[Source], indexes: &[usize]) -> String { indexes.iter().map(|&i| sources[i].service.name()).collect::<Vec<_>>().join(", ") }
+
+
+#[cfg(test)]
+mod tests;
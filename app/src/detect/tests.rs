@@ -0,0 +1,77 @@
+use std::{
+    io::Read,
+    path::PathBuf,
+};
+
+use super::*;
+
+const SPOTIFY: &str = r#"[{"ts": "2022-11-24T19:32:40Z", "ms_played": 203000, "master_metadata_track_name": "Angel With A Shotgun"}]"#;
+const LISTENBRAINZ: &str = r#"{"listened_at": 1669318360, "track_metadata": {"additional_info": {"ms_played": 203000}}}"#;
+
+fn detected(name: &str, head: &str) -> Result<&'static str, Undetected> {
+    let sources = sources().expect("Invalid file patterns");
+    detect(&sources, Path::new(name), head.as_bytes()).map(|i| sources[i].service.name())
+}
+
+fn ambiguous(names: &str) -> Result<&'static str, Undetected> { Err(Undetected::Ambiguous(names.to_owned())) }
+
+#[test]
+fn test_detect_marker() {
+    assert_eq!(detected("anything.json", SPOTIFY), Ok("spotify"));
+    assert_eq!(detected("scrobbler.log", "#AUDIOSCROBBLER/1.1\n#TZ/UTC\n"), Ok("scrobbler-log"));
+    assert_eq!(detected("export.csv", "uts,utc_time,artist,artist_mbid,album,album_mbid,track,track_mbid\n"), Ok("lastfm"));
+}
+
+#[test]
+fn test_detect_markers_by_name() {
+    assert_eq!(detected("export/listens/2023/1.jsonl", LISTENBRAINZ), Ok("listenbrainz"));
+    assert_eq!(detected("endsong_0.json", LISTENBRAINZ), Ok("spotify"));
+    assert_eq!(detected("listens.jsonl", LISTENBRAINZ), ambiguous("spotify, listenbrainz"));
+}
+
+#[test]
+fn test_detect_generic_name() {
+    assert_eq!(detected("Userdata.json", r#"{"username": "someone"}"#), Err(Undetected::Unknown));
+    assert_eq!(detected("Playlist1.json", r#"{"playlists": []}"#), Err(Undetected::Unknown));
+    assert_eq!(detected("scrobbles.csv", "The Cab,Symphony Soldier,Angel With A Shotgun,24 Nov 2022 19:32\n"), Err(Undetected::Unknown));
+}
+
+#[test]
+fn test_detect_specific_name() {
+    assert_eq!(detected("com.plexapp.plugins.library.db", "SQLite format 3\0"), Ok("plex"));
+    assert_eq!(detected("watch-history.json.gz", "[]"), Ok("youtube-music"));
+}
+
+#[test]
+fn test_detect_unknown() {
+    assert_eq!(detected("notes.txt", "Nothing to see here"), Err(Undetected::Unknown));
+    assert_eq!(detected("empty", ""), Err(Undetected::Unknown));
+    // Any xlsx file starts with the same zip header, so deezer has to be selected explicitly
+    assert_eq!(detected("deezer_history.xlsx", "PK\x03\x04\x14\0\x06\0[Content_Types].xml"), Err(Undetected::Unknown));
+}
+
+fn input(name: &str, content: &'static str) -> Found { Found::opened(PathBuf::from(name), Box::new(content.as_bytes())) }
+
+#[test]
+fn test_group() {
+    let groups =
+        group([input("endsong_0.json", SPOTIFY), input("Userdata.json", "{}"), input("endsong_1.json", SPOTIFY)].into_iter()).expect("Failed to group files");
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].0.name(), "spotify");
+
+    let mut files = groups.into_iter().next().unwrap().1.into_iter();
+    let (path, mut source) = files.next().unwrap().open().expect("Failed to open file");
+    assert_eq!(path, Path::new("endsong_0.json"));
+    let mut content = String::new();
+    source.read_to_string(&mut content).expect("Failed to read file");
+    assert_eq!(content, SPOTIFY);
+    assert_eq!(files.next().unwrap().path, Path::new("endsong_1.json"));
+}
+
+#[test]
+#[should_panic(expected = "Could not detect the service for any files")]
+fn test_group_nothing_detected() { group([input("Userdata.json", "{}"), input("notes.txt", "")].into_iter()).unwrap(); }
+
+#[test]
+#[should_panic(expected = "Could not choose a service for some files")]
+fn test_group_ambiguous() { group([input("endsong_0.json", SPOTIFY), input("listens.jsonl", LISTENBRAINZ)].into_iter()).unwrap(); }
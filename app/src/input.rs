@@ -0,0 +1,235 @@
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{
+        self,
+        BufRead,
+        BufReader,
+        Cursor,
+        Read,
+        Seek,
+    },
+    iter,
+    path::{
+        Path,
+        PathBuf,
+    },
+    rc::Rc,
+};
+
+use anyhow::{
+    Context,
+    Result,
+};
+use flate2::bufread::MultiGzDecoder;
+use liblzma::bufread::XzDecoder;
+use regex::Regex;
+use zip::ZipArchive;
+
+use crate::print_err;
+
+/// Path used in place of a file to read from stdin
+pub(crate) const STDIN: &str = "-";
+
+/// How much of the start of a file is read by [`Found::peek`]
+const HEAD_LEN: u64 = 8 * 1024;
+
+/// A file to import, along with the path it was read from
+pub(crate) type Input = (PathBuf, Box<dyn Read>);
+
+/// A file found to import, which isn't opened until it's read
+pub(crate) struct Found {
+    pub(crate) path: PathBuf,
+    source: Source,
+}
+
+enum Source {
+    /// A reader that can only be read once, such as stdin
+    Opened(RefCell<Option<Box<dyn Read>>>),
+    /// Opens a new reader each time it's called
+    Lazy(Box<dyn Fn() -> io::Result<Box<dyn Read>>>),
+}
+
+impl Found {
+    pub(crate) fn opened(path: PathBuf, reader: Box<dyn Read>) -> Self {
+        Self {
+            path,
+            source: Source::Opened(RefCell::new(Some(reader))),
+        }
+    }
+
+    fn lazy(path: PathBuf, open: impl Fn() -> io::Result<Box<dyn Read>> + 'static) -> Self {
+        Self {
+            path,
+            source: Source::Lazy(Box::new(open)),
+        }
+    }
+
+    fn read(&self) -> Result<Box<dyn Read>> {
+        let reader = match self.source {
+            Source::Opened(ref reader) => reader.take().context("Already read"),
+            Source::Lazy(ref open) => Ok(open()?),
+        };
+        reader.with_context(|| self.path.display().to_string())
+    }
+
+    /// Read the start of the file. Files that can be opened again are closed afterwards, otherwise the start is kept
+    /// in memory to be read again
+    pub(crate) fn peek(&self) -> Result<Vec<u8>> {
+        let mut reader = self.read()?;
+        let mut head = Vec::new();
+        reader
+            .by_ref()
+            .take(HEAD_LEN)
+            .read_to_end(&mut head)
+            .with_context(|| self.path.display().to_string())?;
+        if let Source::Opened(ref opened) = self.source {
+            opened.replace(Some(Box::new(Cursor::new(head.clone()).chain(reader))));
+        }
+        Ok(head)
+    }
+
+    /// Open the file to import it. Files that can't be opened are reported and skipped
+    pub(crate) fn open(self) -> Option<Input> {
+        if self.path.as_os_str() == STDIN {
+            println!("Importing from stdin");
+        } else {
+            println!("Importing file '{}'", self.path.display());
+        }
+        let reader = self.read().inspect_err(print_err).ok()?;
+        Some((self.path, reader))
+    }
+}
+
+/// Build a regex from a service file pattern, matching against the end of a `/` separated path with an optional
+/// compression extension. Patterns may include directories to only match files in those directories
+pub(crate) fn file_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(&format!(r"^(?:.*/)?(?:{})(?:\.(?:gz|zst|xz))?$", pattern.replace(" | ", "|"))).context("Invalid file pattern")
+}
+
+/// Whether the end of `path` matches a regex from [`file_regex`]
+pub(crate) fn is_match(files: &Regex, path: &Path) -> bool {
+    let path: Vec<_> = path.iter().map(|c| c.to_string_lossy()).collect();
+    files.is_match(&path.join("/"))
+}
+
+/// Open each of `paths`, as found by [`find`]. Files that can't be opened are reported and skipped
+pub(crate) fn open<'a>(paths: &'a [PathBuf], files: &'a Regex) -> impl Iterator<Item = Input> + 'a { find(paths, files).filter_map(Found::open) }
+
+/// Find the files to import from each of `paths`. Directories are searched recursively for files with names matching
+/// `files`, and zip archives are expanded into the matching files inside them. Gzip, zstd, or xz compressed files are
+/// decompressed when read. A path of [`STDIN`] reads from stdin instead. Archives that can't be opened are reported and
+/// skipped
+pub(crate) fn find<'a>(paths: &'a [PathBuf], files: &'a Regex) -> impl Iterator<Item = Found> + 'a {
+    paths.iter().flat_map(move |p| -> Box<dyn Iterator<Item = Found>> {
+        if p.as_os_str() == STDIN {
+            let stdin = decompress(BufReader::new(io::stdin())).with_context(|| p.display().to_string());
+            Box::new(stdin.inspect_err(print_err).ok().map(|r| Found::opened(p.clone(), r)).into_iter())
+        } else if p.is_dir() {
+            let (found, ignored) = walk(p, files);
+            for path in ignored {
+                println!("Ignoring file '{}'", path.display());
+            }
+            Box::new(found.into_iter().flat_map(move |p| find_file(p, files)))
+        } else {
+            find_file(p.clone(), files)
+        }
+    })
+}
+
+fn find_file(path: PathBuf, files: &Regex) -> Box<dyn Iterator<Item = Found>> {
+    if !is_zip(&path) {
+        return Box::new(iter::once(Found::lazy(path.clone(), move || decompress(BufReader::new(File::open(&path)?)))));
+    }
+
+    let archive = File::open(&path)
+        .and_then(|f| Ok(ZipArchive::new(BufReader::new(f))?))
+        .with_context(|| path.display().to_string());
+    match archive {
+        Ok(archive) => Box::new(archive_entries(&path, archive, files).into_iter()),
+        Err(e) => {
+            print_err(&e);
+            Box::new(iter::empty())
+        },
+    }
+}
+
+/// Recursively find all files in `dir` with paths matching `files`, returning them along with all the other files found.
+/// Links to directories are not followed
+fn walk(dir: &Path, files: &Regex) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let (mut found, mut ignored) = (Vec::new(), Vec::new());
+    let entries = match dir.read_dir().with_context(|| dir.display().to_string()) {
+        Ok(entries) => entries,
+        Err(e) => {
+            print_err(&e);
+            return (found, ignored);
+        },
+    };
+    let mut entries: Vec<_> = entries.filter_map(|e| e.and_then(|e| Ok((e.path(), e.file_type()?))).ok()).collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (path, file_type) in entries {
+        if file_type.is_dir() {
+            let (f, i) = walk(&path, files);
+            found.extend(f);
+            ignored.extend(i);
+        } else if (file_type.is_file() || path.is_file()) && is_match(files, &path) {
+            found.push(path);
+        } else {
+            ignored.push(path);
+        }
+    }
+    (found, ignored)
+}
+
+/// Wrap `source` in a decoder if it starts with the magic bytes of a supported compression format
+fn decompress(mut source: impl BufRead + 'static) -> io::Result<Box<dyn Read>> {
+    const GZIP: &[u8] = &[0x1F, 0x8B];
+    const ZSTD: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+    const XZ: &[u8] = &[0xFD, b'7', b'z', b'X', b'Z', 0x00];
+
+    let magic: Vec<u8> = source.fill_buf()?.iter().take(XZ.len()).copied().collect();
+    Ok(if magic.starts_with(GZIP) {
+        Box::new(BufReader::new(MultiGzDecoder::new(source)))
+    } else if magic.starts_with(ZSTD) {
+        Box::new(BufReader::new(zstd::Decoder::with_buffer(source)?))
+    } else if magic.starts_with(XZ) {
+        Box::new(BufReader::new(XzDecoder::new_multi_decoder(source)))
+    } else {
+        Box::new(source)
+    })
+}
+
+fn is_zip(path: &Path) -> bool { path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) }
+
+/// Find each file in `archive` with a path matching `files`.
+///
+/// The archive is kept open so entries can be read when needed, but each one is fully decompressed into memory when
+/// read since it can't outlive the borrow of `archive`
+fn archive_entries(path: &Path, archive: ZipArchive<impl Read + Seek + 'static>, files: &Regex) -> Vec<Found> {
+    let matching: Vec<_> = (0..archive.len())
+        .filter_map(|i| archive.name_for_index(i).filter(|name| files.is_match(name)).map(|name| (i, path.join(name))))
+        .collect();
+    if matching.is_empty() {
+        println!("No files to import found in archive '{}'", path.display());
+    }
+
+    let archive = Rc::new(RefCell::new(archive));
+    matching
+        .into_iter()
+        .map(|(i, name)| {
+            let archive = Rc::clone(&archive);
+            Found::lazy(name, move || {
+                let mut archive = archive.borrow_mut();
+                let mut entry = archive.by_index(i)?;
+                let mut buf = Vec::with_capacity(entry.size() as usize);
+                entry.read_to_end(&mut buf)?;
+                decompress(Cursor::new(buf))
+            })
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests;
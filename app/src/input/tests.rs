@@ -0,0 +1,217 @@
+use std::io::Write;
+
+use zip::{
+    write::SimpleFileOptions,
+    ZipWriter,
+};
+
+use super::*;
+use crate::args::{
+    AudiobookPolicy,
+    Service,
+    SpotifyArgs,
+};
+
+fn archive(names: &[&str]) -> ZipArchive<Cursor<Vec<u8>>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for name in names {
+        zip.start_file(*name, SimpleFileOptions::default()).expect("Failed to start zip entry");
+        zip.write_all(name.as_bytes()).expect("Failed to write zip entry");
+    }
+    let buf = zip.finish().expect("Failed to finish zip").into_inner();
+    ZipArchive::new(Cursor::new(buf)).expect("Failed to read zip")
+}
+
+fn entries(service: &Service, names: &[&str]) -> Vec<String> {
+    let files = file_regex(service.file_pattern()).expect("Invalid file pattern");
+    archive_entries(Path::new("export.zip"), archive(names), &files)
+        .into_iter()
+        .map(|found| {
+            let (path, mut source) = found.open().expect("Failed to open entry");
+            let mut content = String::new();
+            source.read_to_string(&mut content).expect("Failed to read entry");
+            assert_eq!(path, Path::new("export.zip").join(&content));
+            content
+        })
+        .collect()
+}
+
+#[test]
+fn test_archive_entries_listenbrainz() {
+    let names = [
+        "listens/2022/11.jsonl",
+        "export/listens/2023/1.jsonl",
+        "feedback.jsonl",
+        "listens/2022/11.json",
+        "other/2022/11.jsonl",
+        "1.jsonl",
+    ];
+    assert_eq!(entries(&Service::ListenBrainz, &names), ["listens/2022/11.jsonl", "export/listens/2023/1.jsonl"]);
+}
+
+#[test]
+fn test_archive_entries_by_name() {
+    let names = [
+        "Spotify Extended Streaming History/Streaming_History_Audio_2023_1.json",
+        "Spotify Extended Streaming History/ReadMeFirst.pdf",
+        "Spotify Account Data/Userdata.json",
+        "Spotify Account Data/StreamingHistory_music_0.json",
+    ];
+    let spotify = Service::Spotify(SpotifyArgs {
+        include_podcasts: false,
+        audiobooks: AudiobookPolicy::Skip,
+    });
+    assert_eq!(entries(&spotify, &names), [
+        "Spotify Extended Streaming History/Streaming_History_Audio_2023_1.json",
+        "Spotify Account Data/StreamingHistory_music_0.json",
+    ]);
+}
+
+const CONTENT: &str = r#"[{"ts": "2022-11-24T19:32:40Z", "ms_played": 203000}]"#;
+
+fn decompressed(bytes: Vec<u8>) -> String {
+    let mut source = decompress(Cursor::new(bytes)).expect("Failed to open input");
+    let mut content = String::new();
+    source.read_to_string(&mut content).expect("Failed to decompress");
+    content
+}
+
+#[test]
+fn test_decompress_gzip() {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(CONTENT.as_bytes()).expect("Failed to compress");
+    let bytes = encoder.finish().expect("Failed to compress");
+    assert_eq!(decompressed(bytes), CONTENT);
+}
+
+#[test]
+fn test_decompress_zstd() {
+    let bytes = zstd::encode_all(CONTENT.as_bytes(), 0).expect("Failed to compress");
+    assert_eq!(decompressed(bytes), CONTENT);
+}
+
+#[test]
+fn test_decompress_xz() {
+    let mut encoder = liblzma::write::XzEncoder::new(Vec::new(), 6);
+    encoder.write_all(CONTENT.as_bytes()).expect("Failed to compress");
+    let bytes = encoder.finish().expect("Failed to compress");
+    assert_eq!(decompressed(bytes), CONTENT);
+}
+
+#[test]
+fn test_decompress_uncompressed() {
+    assert_eq!(decompressed(CONTENT.into()), CONTENT);
+}
+
+#[test]
+fn test_file_regex_compressed() {
+    let files = file_regex(Service::ListenBrainz.file_pattern()).expect("Invalid file pattern");
+    assert!(files.is_match("listens/2023/1.jsonl"));
+    assert!(files.is_match("listens/2023/1.jsonl.gz"));
+    assert!(files.is_match("listens/2023/1.jsonl.zst"));
+    assert!(files.is_match("listens/2023/1.jsonl.xz"));
+    assert!(!files.is_match("listens/2023/1.jsonl.bz2"));
+    assert!(!files.is_match("listens/2023/1.jsonl.gz.xz"));
+}
+
+fn tree(name: &str, files: &[&str]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("lb-importer-walk-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for file in files {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).expect("Failed to create directory");
+        std::fs::write(path, "[]").expect("Failed to write file");
+    }
+    root
+}
+
+#[test]
+fn test_walk() {
+    let root = tree("spotify", &[
+        "Spotify Account Data/Userdata.json",
+        "Spotify Account Data/Playlist1.json",
+        "Spotify Account Data/StreamingHistory_music_0.json",
+        "Spotify Extended Streaming History/Streaming_History_Audio_2023_1.json.gz",
+        "Spotify Extended Streaming History/ReadMeFirst.pdf",
+    ]);
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&root, root.join("Spotify Account Data/loop")).expect("Failed to create link");
+
+    let spotify = Service::Spotify(SpotifyArgs {
+        include_podcasts: false,
+        audiobooks: AudiobookPolicy::Skip,
+    });
+    let (found, ignored) = walk(&root, &file_regex(spotify.file_pattern()).expect("Invalid file pattern"));
+    let relative = |paths: Vec<PathBuf>| paths.into_iter().map(|p| p.strip_prefix(&root).unwrap().to_owned()).collect::<Vec<_>>();
+    assert_eq!(relative(found), [
+        PathBuf::from("Spotify Account Data/StreamingHistory_music_0.json"),
+        PathBuf::from("Spotify Extended Streaming History/Streaming_History_Audio_2023_1.json.gz"),
+    ]);
+    let mut expected = vec![
+        PathBuf::from("Spotify Account Data/Playlist1.json"),
+        PathBuf::from("Spotify Account Data/Userdata.json"),
+        PathBuf::from("Spotify Extended Streaming History/ReadMeFirst.pdf"),
+    ];
+    if cfg!(unix) {
+        expected.insert(2, PathBuf::from("Spotify Account Data/loop"));
+    }
+    assert_eq!(relative(ignored), expected);
+
+    std::fs::remove_dir_all(root).expect("Failed to clean up");
+}
+
+#[test]
+fn test_walk_detectable() {
+    let root = tree("detect", &[
+        "export/listens/2023/1.jsonl",
+        "export/feedback.jsonl",
+        "lastfm/scrobbles.csv",
+        "Takeout/watch-history.json",
+        "Userdata.json",
+    ]);
+    let (found, ignored) = walk(&root, &crate::detect::file_regex().expect("Invalid file pattern"));
+    assert_eq!(found, [root.join("Takeout/watch-history.json"), root.join("export/listens/2023/1.jsonl")]);
+    assert_eq!(ignored, [
+        root.join("Userdata.json"),
+        root.join("export/feedback.jsonl"),
+        root.join("lastfm/scrobbles.csv")
+    ]);
+
+    std::fs::remove_dir_all(root).expect("Failed to clean up");
+}
+
+fn read_all(found: Found) -> String {
+    let (_, mut source) = found.open().expect("Failed to open file");
+    let mut content = String::new();
+    source.read_to_string(&mut content).expect("Failed to read file");
+    content
+}
+
+#[test]
+fn test_found_opened_peek() {
+    let found = Found::opened(PathBuf::from(STDIN), Box::new(CONTENT.as_bytes()));
+    assert_eq!(found.peek().expect("Failed to peek"), CONTENT.as_bytes());
+    assert_eq!(read_all(found), CONTENT);
+}
+
+#[test]
+fn test_find_lazy() {
+    let root = tree("lazy", &["endsong_0.json"]);
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(CONTENT.as_bytes()).expect("Failed to compress");
+    std::fs::write(root.join("endsong_1.json.gz"), encoder.finish().expect("Failed to compress")).expect("Failed to write file");
+
+    let paths = [root.clone()];
+    let files = file_regex(r"endsong_\d+\.json").expect("Invalid file pattern");
+    let found: Vec<_> = find(&paths, &files).collect();
+    assert_eq!(found.len(), 2);
+    // Files are only opened when read, so changes after they're found are still imported
+    std::fs::write(root.join("endsong_0.json"), CONTENT).expect("Failed to write file");
+    let mut found = found.into_iter();
+    assert_eq!(read_all(found.next().unwrap()), CONTENT);
+    let compressed = found.next().unwrap();
+    assert_eq!(compressed.peek().expect("Failed to peek"), CONTENT.as_bytes());
+    assert_eq!(read_all(compressed), CONTENT);
+
+    std::fs::remove_dir_all(root).expect("Failed to clean up");
+}
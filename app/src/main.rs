@@ -2,8 +2,7 @@
 
 use std::{
     fmt::Display,
-    fs::File,
-    io::BufReader,
+    path::Path,
     thread,
     time::Duration,
 };
@@ -14,9 +13,35 @@ use anyhow::{
 };
 use clap::Parser;
 use lb_importer_services::{
+    load_amazon_music,
+    load_apple_music,
+    load_csv,
+    load_deezer,
+    load_jellyfin,
+    load_jellyfin_file,
+    load_json,
+    load_lastfm,
+    load_lastfm_api,
     load_listenbrainz,
+    load_maloja,
+    load_mpdscribble,
+    load_my_activity,
+    load_plex,
+    load_plex_file,
+    load_rockbox,
+    load_scrobbler_log,
     load_spotify,
-    service::spotify::Listen,
+    load_traktor,
+    load_youtube_music,
+    service::{
+        is_sqlite_file,
+        spotify::{
+            book_sessions,
+            FileKind,
+            Kind,
+            Listen,
+        },
+    },
     ListenData,
 };
 use listenbrainz::raw::{
@@ -31,18 +56,49 @@ use listenbrainz::raw::{
 use time::{
     format_description::well_known::Rfc3339,
     OffsetDateTime,
+    UtcOffset,
 };
+use uuid::Uuid;
 
-use crate::args::{
-    Args,
-    Service::{
-        ListenBrainz,
-        Spotify,
+use crate::{
+    args::{
+        Args,
+        AudiobookPolicy,
+        JellyfinArgs,
+        PlexArgs,
+        RockboxArgs,
+        Service::{
+            self,
+            AmazonMusic,
+            AppleMusic,
+            Csv,
+            Deezer,
+            Jellyfin,
+            Json,
+            LastFm,
+            LastFmApi,
+            ListenBrainz,
+            Maloja,
+            Mpdscribble,
+            MyActivity,
+            Plex,
+            Rockbox,
+            ScrobblerLog,
+            Spotify,
+            Traktor,
+            YouTubeMusic,
+        },
+        SpotifyArgs,
+    },
+    input::{
+        Found,
+        Input,
     },
-    SpotifyArgs,
 };
 
 mod args;
+mod detect;
+mod input;
 
 
 fn print_err(e: &impl Display) {
@@ -50,50 +106,162 @@ fn print_err(e: &impl Display) {
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse_from(wild::args_os());
+    let mut args = Args::parse_from(wild::args_os());
 
     #[cfg(debug_assertions)]
     dbg!(&args);
 
-    let client = args.url.map_or_else(Client::new, Client::new_with_url);
+    let client = args.url.take().map_or_else(Client::new, Client::new_with_url);
 
     let token = args.token.as_hyphenated().to_string();
     if !client.validate_token(token.as_str())?.valid {
         return Err(listenbrainz::Error::InvalidToken.into());
     }
 
-    let files = args.files.iter().filter_map(|p| {
-        File::open(p)
-            .map(BufReader::new)
-            .inspect(|_| println!("Importing file '{}'", p.display()))
-            .with_context(|| p.display().to_string())
-            .inspect_err(print_err)
-            .ok()
-    });
+    match args.service.take() {
+        Some(service) => {
+            let file_regex = input::file_regex(service.file_pattern())?;
+            import(service, input::open(&args.files, &file_regex), &args, &client, &token)?;
+        },
+        None => {
+            let file_regex = detect::file_regex()?;
+            for (service, files) in detect::group(input::find(&args.files, &file_regex))? {
+                println!("Importing {} {} file(s)", files.len(), service.name());
+                import(service, files.into_iter().filter_map(Found::open), &args, &client, &token)?;
+            }
+        },
+    }
+
+    anyhow::Ok(())
+}
 
+/// Import all listens from `files`, which must all be from `service`
+fn import(service: Service, files: impl Iterator<Item = Input>, args: &Args, client: &Client, token: &str) -> Result<()> {
     macro_rules! filtered {
-        ($load:expr) => {
+        (|$f:ident, $p:ident| $load:expr) => {
             files
-                .filter_map(|f| $load(f).inspect_err(print_err).ok())
+                .filter_map(|(path, $f)| {
+                    let $p = path.as_path();
+                    $load.inspect_err(print_err).ok()
+                })
                 .flatten()
                 .filter(|ld| args.before.map(|dt| ld.listened_at() < dt.unix_timestamp()).unwrap_or(true))
                 .filter(|ld| args.after.map(|dt| dt.unix_timestamp() < ld.listened_at()).unwrap_or(true))
         };
+        ($load:expr) => {
+            filtered!(|f, _p| $load(f))
+        };
     }
+    let local_offset = || args.utc_offset.map_or_else(UtcOffset::current_local_offset, Ok);
     macro_rules! submit {
         ($it:expr) => {
-            submit($it, args.batch_size, &client, &token);
+            submit($it, args.batch_size, client, token);
         };
     }
-    match args.service {
+    match service {
         ListenBrainz => {
             submit!(filtered!(load_listenbrainz));
         },
-        Spotify(SpotifyArgs { min_play_time }) => {
-            let mut listens: Vec<_> = filtered!(load_spotify).filter(|l| l.ms_played >= u32::from(min_play_time * 1000)).collect();
+        LastFm => {
+            submit!(filtered!(load_lastfm));
+        },
+        LastFmApi => {
+            submit!(filtered!(load_lastfm_api));
+        },
+        AppleMusic => {
+            submit!(filtered!(load_apple_music).filter(|l| l.play_duration_ms >= i64::from(args.min_play_time) * 1000 && !l.is_skipped()));
+        },
+        YouTubeMusic => {
+            submit!(filtered!(load_youtube_music));
+        },
+        ScrobblerLog => {
+            let local_offset = local_offset()?;
+            submit!(filtered!(|f| load_scrobbler_log(f, local_offset)));
+        },
+        Maloja => {
+            submit!(filtered!(load_maloja));
+        },
+        Deezer => {
+            submit!(filtered!(load_deezer).filter(|l| l.listening_time >= u32::from(args.min_play_time)));
+        },
+        Jellyfin(JellyfinArgs { user }) => {
+            let local_offset = local_offset()?;
+            submit!(filtered!(|f, p| if is_sqlite_file(p) {
+                load_jellyfin_file(p, local_offset)
+            } else {
+                warn_sqlite_copy(p);
+                load_jellyfin(f, local_offset)
+            })
+            .filter(|l| user.is_none_or(|u| Uuid::parse_str(&l.user_id).is_ok_and(|id| id == u)))
+            .filter(|l| l.play_duration >= u32::from(args.min_play_time)));
+        },
+        Plex(PlexArgs { account }) => {
+            submit!(filtered!(|f, p| if is_sqlite_file(p) {
+                load_plex_file(p)
+            } else {
+                warn_sqlite_copy(p);
+                load_plex(f)
+            })
+            .filter(|l| account.is_none_or(|a| a == l.account_id)));
+        },
+        Mpdscribble => {
+            submit!(filtered!(load_mpdscribble));
+        },
+        Csv(csv) => {
+            let mapping = csv.mapping()?;
+            let local_offset = local_offset()?;
+            submit!(filtered!(|f| load_csv(f, &mapping, local_offset)));
+        },
+        Json(json) => {
+            let mapping = json.mapping()?;
+            let local_offset = local_offset()?;
+            submit!(filtered!(|f| load_json(f, &mapping, local_offset).map(|(listens, errors)| {
+                errors.iter().for_each(print_err);
+                listens
+            })));
+        },
+        Traktor => {
+            let local_offset = local_offset()?;
+            submit!(filtered!(|f| load_traktor(f, local_offset)).filter(|l| l.duration.is_none_or(|d| d >= u32::from(args.min_play_time))));
+        },
+        Rockbox(RockboxArgs { library, min_ratio }) => {
+            let local_offset = local_offset()?;
+            submit!(filtered!(|f| load_rockbox(f, &library, local_offset))
+                .filter(|l| l.played_ratio() >= min_ratio && l.elapsed_ms >= u64::from(args.min_play_time) * 1000));
+        },
+        MyActivity => {
+            let local_offset = local_offset()?;
+            submit!(filtered!(|f| load_my_activity(f, local_offset)));
+        },
+        AmazonMusic => {
+            submit!(filtered!(load_amazon_music).filter(|l| l.duration_ms >= i64::from(args.min_play_time) * 1000 && !l.is_skipped()));
+        },
+        Spotify(SpotifyArgs { include_podcasts, audiobooks }) => {
+            let mut listens: Vec<_> = filtered!(|f, p| {
+                let kind = FileKind::from_path(p);
+                match kind {
+                    FileKind::Video => println!("Skipping spotify video history"),
+                    FileKind::Podcast if !include_podcasts => {
+                        println!("Skipping spotify podcast history, use --include-podcasts to import it");
+                        return None;
+                    },
+                    _ => {},
+                }
+                load_spotify(f, kind)
+            })
+            .filter(|l| match l.kind {
+                Kind::Track => true,
+                Kind::Episode => include_podcasts,
+                Kind::Audiobook => audiobooks != AudiobookPolicy::Skip,
+            })
+            .filter(|l| l.ms_played >= u32::from(args.min_play_time * 1000))
+            .collect();
             listens.sort_unstable_by_key(ListenData::listened_at);
+            if audiobooks == AudiobookPolicy::Books {
+                listens = book_sessions(listens);
+            }
 
-            let listens = dedup_spotify(&listens, u64::from(min_play_time));
+            let listens = dedup_spotify(&listens, u64::from(args.min_play_time));
 
             submit!(listens.into_iter());
         },
@@ -102,6 +270,11 @@ fn main() -> Result<()> {
     anyhow::Ok(())
 }
 
+/// Databases that aren't read in place are copied into memory, which only includes the main database file
+fn warn_sqlite_copy(path: &Path) {
+    eprintln!("Warning: '{}' is not a database file on disk, so any changes still in its `-wal` file are not included", path.display());
+}
+
 fn dedup_spotify(listens: &Vec<Listen>, time_threshold: u64) -> Vec<&Listen> {
     // const ALL_REASONS: [&str; 13] = ["appload","backbtn","clickrow","endplay","fwdbtn","logout","playbtn","remote","trackdone","trackerror","unexpected-exit","unexpected-exit-while-paused","unknown"];
     const SKIP_REASONS: [&str; 4] = ["logout", "remote", "trackerror", "unknown"];
@@ -112,9 +285,7 @@ fn dedup_spotify(listens: &Vec<Listen>, time_threshold: u64) -> Vec<&Listen> {
 
     listens.iter().rev().fold(Vec::with_capacity(listens.len()), |mut acc, l| {
         if let Some(&prev) = acc.last() {
-            if prev.spotify_track_uri != l.spotify_track_uri
-                || !(is_skip_reason(&l.reason_end) || prev.listened_at().abs_diff(l.listened_at()) <= time_threshold)
-            {
+            if prev.spotify_uri != l.spotify_uri || !(is_skip_reason(&l.reason_end) || prev.listened_at().abs_diff(l.listened_at()) <= time_threshold) {
                 acc.push(l);
             } else {
                 eprintln!("Ignoring duplicate listen for `{}` by `{}`", l.track_name(), l.artist_name());
@@ -1,17 +1,92 @@
 pub use lb_importer_core::*;
 
 use crate::service::{
+    amazon_music::ListenVec as AmazonMusicListenVec,
+    apple_music::ListenVec as AppleMusicListenVec,
+    deezer::ListenVec as DeezerListenVec,
+    generic_csv::ListenVec as GenericCsvListenVec,
+    generic_json::ListenVec as GenericJsonListenVec,
+    jellyfin::ListenVec as JellyfinListenVec,
+    lastfm::ListenVec as LastfmListenVec,
+    lastfm_api::ListenVec as LastfmApiListenVec,
     listenbrainz::ListenVec as LBListenVec,
+    maloja::ListenVec as MalojaListenVec,
+    mpdscribble::ListenVec as MpdscribbleListenVec,
+    my_activity::ListenVec as MyActivityListenVec,
+    plex::ListenVec as PlexListenVec,
+    rockbox::ListenVec as RockboxListenVec,
+    scrobbler_log::ListenVec as ScrobblerLogListenVec,
     spotify::ListenVec as SpotifyListenVec,
+    traktor::ListenVec as TraktorListenVec,
+    youtube_music::ListenVec as YouTubeMusicListenVec,
 };
 
 pub mod service;
 
 macro_rules! load_fn {
     ($name:ident, $ty:path) => {
-        pub fn $name(source: impl std::io::Read) -> anyhow::Result<$ty> { serde_json::from_reader(source).map_err(Into::into) }
+        load_fn!($name, $ty, serde_json::from_reader);
     };
+    ($name:ident, $ty:path, $parse:path) => {
+        pub fn $name(source: impl std::io::Read) -> anyhow::Result<$ty> { $parse(source).map_err(Into::into) }
+    };
+}
+
+/// How the file is parsed depends on `kind`, which can be detected from the file name with [`service::spotify::FileKind::from_path`]
+pub fn load_spotify(source: impl std::io::Read, kind: service::spotify::FileKind) -> anyhow::Result<SpotifyListenVec> { service::spotify::parse(source, kind) }
+load_fn!(load_listenbrainz, LBListenVec, service::listenbrainz::parse);
+load_fn!(load_lastfm, LastfmListenVec, service::lastfm::parse);
+load_fn!(load_lastfm_api, LastfmApiListenVec, service::lastfm_api::parse);
+load_fn!(load_apple_music, AppleMusicListenVec, service::apple_music::parse);
+load_fn!(load_youtube_music, YouTubeMusicListenVec, service::youtube_music::parse);
+
+/// Timestamps from logs without a known timezone are converted to UTC using `local_offset`
+pub fn load_scrobbler_log(source: impl std::io::Read, local_offset: time::UtcOffset) -> anyhow::Result<ScrobblerLogListenVec> {
+    service::scrobbler_log::parse(source, local_offset)
+}
+load_fn!(load_maloja, MalojaListenVec, service::maloja::parse);
+load_fn!(load_deezer, DeezerListenVec, service::deezer::parse);
+
+/// Plays are recorded in the local time of the server, which is converted to UTC using `local_offset`
+pub fn load_jellyfin(source: impl std::io::Read, local_offset: time::UtcOffset) -> anyhow::Result<JellyfinListenVec> {
+    service::jellyfin::parse(source, local_offset)
+}
+/// Includes plays from the database's `-wal` file, unlike [`load_jellyfin`]
+pub fn load_jellyfin_file(path: &std::path::Path, local_offset: time::UtcOffset) -> anyhow::Result<JellyfinListenVec> {
+    service::jellyfin::parse_file(path, local_offset)
+}
+load_fn!(load_plex, PlexListenVec, service::plex::parse);
+/// Includes views from the database's `-wal` file, unlike [`load_plex`]
+pub fn load_plex_file(path: &std::path::Path) -> anyhow::Result<PlexListenVec> { service::plex::parse_file(path) }
+load_fn!(load_mpdscribble, MpdscribbleListenVec, service::mpdscribble::parse);
+
+/// Columns are read using `mapping`, and timestamps without an offset are converted to UTC using `local_offset`
+pub fn load_csv(source: impl std::io::Read, mapping: &service::generic_csv::Mapping, local_offset: time::UtcOffset) -> anyhow::Result<GenericCsvListenVec> {
+    service::generic_csv::parse(source, mapping, local_offset)
+}
+
+/// Values are read using `mapping`, and timestamps without an offset are converted to UTC using `local_offset`.
+/// Records that were skipped are returned along with the reason
+pub fn load_json(
+    source: impl std::io::Read,
+    mapping: &service::generic_json::Mapping,
+    local_offset: time::UtcOffset,
+) -> anyhow::Result<(GenericJsonListenVec, Vec<service::generic_json::RecordError>)> {
+    service::generic_json::parse(source, mapping, local_offset)
+}
+
+/// Tracks are recorded in the local time of the computer, which is converted to UTC using `local_offset`
+pub fn load_traktor(source: impl std::io::Read, local_offset: time::UtcOffset) -> anyhow::Result<TraktorListenVec> {
+    service::traktor::parse(source, local_offset)
+}
+
+/// Tags are read from the played files found in `library`, and timestamps are converted to UTC using `local_offset`
+pub fn load_rockbox(source: impl std::io::Read, library: &std::path::Path, local_offset: time::UtcOffset) -> anyhow::Result<RockboxListenVec> {
+    service::rockbox::parse(source, library, local_offset)
 }
 
-load_fn!(load_spotify, SpotifyListenVec);
-load_fn!(load_listenbrainz, LBListenVec);
+/// Dates with an unknown timezone are converted to UTC using `local_offset`
+pub fn load_my_activity(source: impl std::io::Read, local_offset: time::UtcOffset) -> anyhow::Result<MyActivityListenVec> {
+    service::my_activity::parse(source, local_offset)
+}
+load_fn!(load_amazon_music, AmazonMusicListenVec, service::amazon_music::parse);
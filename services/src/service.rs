@@ -1,11 +1,52 @@
-use std::vec::IntoIter;
+use std::{
+    fmt::Display,
+    fs::File,
+    io::Read,
+    path::Path,
+    str::FromStr,
+    vec::IntoIter,
+};
 
 use ::listenbrainz::raw::request::Payload;
-use serde::Deserialize;
+use rusqlite::{
+    Connection,
+    OpenFlags,
+    MAIN_DB,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
 use serde_json::Value;
+use time::{
+    format_description::{
+        self,
+        well_known::Rfc3339,
+        OwnedFormatItem,
+    },
+    OffsetDateTime,
+    PrimitiveDateTime,
+    UtcOffset,
+};
 
+pub mod amazon_music;
+pub mod apple_music;
+pub mod deezer;
+pub mod generic_csv;
+pub mod generic_json;
+pub mod jellyfin;
+pub mod lastfm;
+pub mod lastfm_api;
 pub mod listenbrainz;
+pub mod maloja;
+pub mod mpdscribble;
+pub mod my_activity;
+pub mod plex;
+pub mod rockbox;
+pub mod scrobbler_log;
 pub mod spotify;
+pub mod traktor;
+pub mod youtube_music;
 
 
 fn additional_info<T: serde::Serialize>(data: &T) -> Option<serde_json::Map<String, Value>> {
@@ -19,6 +60,106 @@ fn additional_info<T: serde::Serialize>(data: &T) -> Option<serde_json::Map<Stri
     })
 }
 
+/// Musicbrainz ids serialized into `additional_info` using the same keys as a listenbrainz dump
+#[derive(Serialize)]
+pub struct MbidInfo<'i> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recording_mbid: Option<&'i str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release_mbid: Option<&'i str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artist_mbids: Vec<&'i str>,
+}
+
+impl<'i> MbidInfo<'i> {
+    #[inline]
+    fn new(recording_mbid: Option<&'i str>, release_mbid: Option<&'i str>, artist_mbids: impl IntoIterator<Item = &'i str>) -> Self {
+        Self {
+            recording_mbid,
+            release_mbid,
+            artist_mbids: artist_mbids.into_iter().collect(),
+        }
+    }
+}
+
+
+/// How timestamps are formatted in a user mapped export
+#[derive(Debug, Clone, Default)]
+pub enum TimestampFormat {
+    /// Seconds since the unix epoch
+    #[default]
+    Unix,
+    /// Milliseconds since the unix epoch
+    UnixMillis,
+    Rfc3339,
+    /// A [`time` format description](https://time-rs.github.io/book/api/format-description.html).
+    /// Times without an offset are assumed to be local
+    Custom(OwnedFormatItem),
+}
+
+impl TimestampFormat {
+    /// Parse `value` into a unix timestamp
+    pub fn parse(&self, value: &str, local_offset: UtcOffset) -> Result<i64, String> {
+        let value = value.trim();
+        let invalid = |e: &dyn Display| format!("Invalid timestamp `{value}`: {e}");
+        match self {
+            Self::Unix => value.parse().map_err(|e| invalid(&e)),
+            Self::UnixMillis => value.parse().map(|ms: i64| ms / 1000).map_err(|e| invalid(&e)),
+            Self::Rfc3339 => OffsetDateTime::parse(value, &Rfc3339)
+                .map(OffsetDateTime::unix_timestamp)
+                .map_err(|e| invalid(&e)),
+            Self::Custom(fmt) => OffsetDateTime::parse(value, fmt)
+                .or_else(|_| PrimitiveDateTime::parse(value, fmt).map(|dt| dt.assume_offset(local_offset)))
+                .map(OffsetDateTime::unix_timestamp)
+                .map_err(|e| invalid(&e)),
+        }
+    }
+}
+
+impl FromStr for TimestampFormat {
+    type Err = time::error::InvalidFormatDescription;
+
+    /// One of `unix`, `unix-ms`, `rfc3339`, or a custom format description
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "unix" => Self::Unix,
+            "unix-ms" => Self::UnixMillis,
+            "rfc3339" => Self::Rfc3339,
+            _ => Self::Custom(format_description::parse_owned::<2>(s)?),
+        })
+    }
+}
+
+
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// Whether `path` is an uncompressed sqlite database on disk, which can be read in place rather than copied into memory
+pub fn is_sqlite_file(path: &Path) -> bool {
+    let mut magic = [0; SQLITE_MAGIC.len()];
+    File::open(path).and_then(|mut f| f.read_exact(&mut magic)).is_ok() && magic == SQLITE_MAGIC
+}
+
+/// Open a sqlite database on disk without modifying it, including any changes still in its `-wal` file
+fn open_sqlite_file(path: &Path) -> anyhow::Result<Connection> { Ok(Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?) }
+
+/// Load a sqlite database into memory so it can be queried without needing a path on disk.
+///
+/// Only the main database file is read, so any changes still in its `-wal` file are missed. Use [`open_sqlite_file`]
+/// instead whenever the database is available on disk
+fn open_sqlite(mut source: impl Read) -> anyhow::Result<Connection> {
+    let mut buf = Vec::new();
+    source.read_to_end(&mut buf)?;
+    // An in-memory database can't use a WAL, so switch the header back to the legacy journal mode
+    if buf.starts_with(SQLITE_MAGIC) && buf.len() > 19 {
+        buf[18] = 1;
+        buf[19] = 1;
+    }
+
+    let mut conn = Connection::open_in_memory()?;
+    conn.deserialize_read_exact(MAIN_DB, buf.as_slice(), buf.len(), true)?;
+    Ok(conn)
+}
+
 
 /// Deserialization wrapper for a `Vec<_>` that will skip errors
 #[serde_with::serde_as]
@@ -28,6 +169,10 @@ impl<T: PayloadT> From<ListenVec<T>> for Vec<T> {
     #[inline]
     fn from(value: ListenVec<T>) -> Self { value.0 }
 }
+impl<T: PayloadT> FromIterator<T> for ListenVec<T> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self { Self(iter.into_iter().collect()) }
+}
 impl<T: PayloadT> IntoIterator for ListenVec<T> {
     type IntoIter = IntoIter<Self::Item>;
     type Item = T;
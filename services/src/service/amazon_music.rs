@@ -0,0 +1,112 @@
+use std::io::Read;
+
+use csv::Reader;
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use time::{
+    format_description::{
+        well_known::Rfc3339,
+        FormatItem,
+    },
+    macros::format_description,
+    OffsetDateTime,
+    PrimitiveDateTime,
+};
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+/// End events that indicate a track wasn't played through, so it isn't a real listen
+const SKIP_EVENTS: [&str; 4] = ["playbackError", "playbackFailed", "skipNext", "skipPrevious"];
+
+
+/// Represents a single row from an amazon music listening history export
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    #[serde(rename = "timestamp", deserialize_with = "parse_datetime")]
+    time: OffsetDateTime,
+
+    #[serde(rename = "title")]
+    pub track: String,
+
+    pub artist: String,
+
+    #[release]
+    pub album: Option<String>,
+
+    #[serde(rename = "consumptionDurationMs")]
+    pub duration_ms: i64,
+
+    #[serde(rename = "endEvent")]
+    pub end_event: Option<String>,
+}
+
+impl Listen {
+    /// Whether playback ended because of an error or a skip rather than finishing or being stopped by the user
+    pub fn is_skipped(&self) -> bool { self.end_event.as_deref().is_some_and(|e| SKIP_EVENTS.contains(&e)) }
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = Info;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time.unix_timestamp() }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(Info {
+            music_service: "music.amazon.com",
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Info {
+    music_service: &'static str,
+}
+
+
+/// Parse an amazon music listening history csv. Rows that fail to parse, or have no artist name, are skipped.
+pub fn parse(source: impl Read) -> csv::Result<ListenVec> {
+    Ok(Reader::from_reader(source)
+        .into_deserialize::<Listen>()
+        .filter_map(Result::ok)
+        .filter(|l| !l.artist.is_empty())
+        .collect())
+}
+
+fn parse_datetime<'de, D>(de: D) -> Result<OffsetDateTime, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    const FMTS: &[&[FormatItem]] = &[
+        format_description!("[year]-[month]-[day] [hour]:[minute]:[second] UTC"),
+        format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+    ];
+
+    let val = String::deserialize(de)?;
+    OffsetDateTime::parse(&val, &Rfc3339)
+        .or_else(|e| {
+            FMTS.iter()
+                .find_map(|fmt| PrimitiveDateTime::parse(&val, fmt).ok())
+                .map(PrimitiveDateTime::assume_utc)
+                .ok_or(e)
+        })
+        .map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests;
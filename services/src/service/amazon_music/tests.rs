@@ -0,0 +1,50 @@
+use time::macros::datetime;
+
+use super::*;
+
+const SAMPLE: &str = "\
+timestamp,deviceType,title,artistAsin,artist,album,consumptionDurationMs,endEvent
+2022-11-24T19:32:40.617Z,ANDROID,Angel With A Shotgun,B000QJPLTS,The Cab,Symphony Soldier,203000,trackFinished
+2018-07-10 06:58:55 UTC,WEB,Burn Brighter,B001234567,Lansdowne,,1200,playbackError
+2018-07-10 07:00:00 UTC,WEB,No Artist,B001234567,,,220000,trackFinished
+";
+
+#[test]
+fn test_de() {
+    let expected = Listen {
+        time: datetime!(2022-11-24 19:32:40.617 UTC),
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        album: Some("Symphony Soldier".to_owned()),
+        duration_ms: 203_000,
+        end_event: Some("trackFinished".to_owned()),
+    };
+
+    let list = parse(SAMPLE.as_bytes()).expect("Failed to parse csv");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+    assert!(!list.0[0].is_skipped());
+}
+
+#[test]
+fn test_de_skipped() {
+    let list = parse(SAMPLE.as_bytes()).expect("Failed to parse csv");
+    assert_eq!(list.0[1].time, datetime!(2018-07-10 06:58:55 UTC));
+    assert_eq!(list.0[1].release_name(), None);
+    assert!(list.0[1].is_skipped());
+}
+
+#[test]
+fn test_skip_events() {
+    let mut listen = parse(SAMPLE.as_bytes()).expect("Failed to parse csv").0.remove(0);
+    for event in SKIP_EVENTS {
+        listen.end_event = Some(event.to_owned());
+        assert!(listen.is_skipped(), "{event} should be skipped");
+    }
+    for event in ["trackFinished", "userStopped", "PLAYBACKERROR", "unknown"] {
+        listen.end_event = Some(event.to_owned());
+        assert!(!listen.is_skipped(), "{event} should not be skipped");
+    }
+    listen.end_event = None;
+    assert!(!listen.is_skipped());
+}
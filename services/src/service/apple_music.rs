@@ -0,0 +1,110 @@
+use std::io::Read;
+
+use csv::{
+    Reader,
+    StringRecord,
+};
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use time::OffsetDateTime;
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+/// End reasons that indicate an event isn't a real listen
+const SKIP_REASONS: [&str; 3] = ["FAILED_TO_LOAD", "NOT_APPLICABLE", "SCRUB_END"];
+
+/// Columns that are only used when the preferred column is missing from the export
+const FALLBACK_HEADERS: [(&str, &str); 3] = [
+    ("Song Name", "Content Name"),
+    ("Artist Name", "Container Artist Name"),
+    ("Album Name", "Container Album Name"),
+];
+
+
+/// Represents a single row from an apple music play activity export
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    #[serde(rename = "Event Start Timestamp", with = "time::serde::rfc3339")]
+    time: OffsetDateTime,
+
+    #[serde(rename = "Song Name")]
+    pub track: String,
+
+    #[serde(rename = "Artist Name")]
+    pub artist: String,
+
+    #[release]
+    #[serde(rename = "Album Name")]
+    pub album: Option<String>,
+
+    #[serde(rename = "Play Duration Milliseconds")]
+    pub play_duration_ms: i64,
+
+    #[serde(rename = "Media Duration In Milliseconds")]
+    pub duration_ms: Option<i64>,
+
+    #[serde(rename = "End Reason Type")]
+    pub end_reason: Option<String>,
+}
+
+impl Listen {
+    /// Whether this event ended in a way that means it shouldn't count as a listen
+    pub fn is_skipped(&self) -> bool { self.end_reason.as_deref().is_some_and(|re| SKIP_REASONS.contains(&re)) }
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = Info;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time.unix_timestamp() }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(Info {
+            music_service: "music.apple.com",
+            duration_ms: self.duration_ms.filter(|&d| d > 0),
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Info {
+    music_service: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<i64>,
+}
+
+
+/// Parse an `Apple Music Play Activity.csv` export. Rows that fail to parse are skipped.
+pub fn parse(source: impl Read) -> csv::Result<ListenVec> {
+    let mut reader = Reader::from_reader(source);
+    let headers = reader.headers()?;
+    let headers: StringRecord = headers
+        .iter()
+        .map(|h| {
+            FALLBACK_HEADERS
+                .iter()
+                .find(|(preferred, fallback)| h == *fallback && !headers.iter().any(|h| h == *preferred))
+                .map_or(h, |(preferred, _)| preferred)
+        })
+        .collect();
+
+    Ok(reader.records().filter_map(|r| r.ok()?.deserialize(Some(&headers)).ok()).collect())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,44 @@
+use time::macros::datetime;
+
+use super::*;
+
+const SAMPLE: &str = "\
+Album Name,Artist Name,Event Start Timestamp,Song Name,Play Duration Milliseconds,Media Duration In Milliseconds,End Reason Type,Event Type
+Symphony Soldier,The Cab,2022-11-24T19:32:40.617Z,Angel With A Shotgun,203000,203456,NATURAL_END_OF_TRACK,PLAY_END
+Symphony Soldier,The Cab,,Angel With A Shotgun,,203456,,LYRIC_DISPLAY
+No Home but the Road,Lansdowne,2018-07-10T06:58:55Z,Burn Brighter,1200,220000,SCRUB_END,PLAY_END
+";
+
+const CONTAINER_SAMPLE: &str = "\
+Container Album Name,Container Artist Name,Event Start Timestamp,Content Name,Play Duration Milliseconds,End Reason Type
+Symphony Soldier,The Cab,2022-11-24T19:32:40Z,Angel With A Shotgun,203000,TRACK_SKIPPED_FORWARDS
+";
+
+#[test]
+fn test_de() {
+    let expected = Listen {
+        time: datetime!(2022-11-24 19:32:40.617 UTC),
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        album: Some("Symphony Soldier".to_owned()),
+        play_duration_ms: 203_000,
+        duration_ms: Some(203_456),
+        end_reason: Some("NATURAL_END_OF_TRACK".to_owned()),
+    };
+
+    let list = parse(SAMPLE.as_bytes()).expect("Failed to parse csv");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+    assert!(!list.0[0].is_skipped());
+    assert!(list.0[1].is_skipped());
+}
+
+#[test]
+fn test_de_fallback_headers() {
+    let list = parse(CONTAINER_SAMPLE.as_bytes()).expect("Failed to parse csv");
+    assert_eq!(list.0.len(), 1);
+    assert_eq!(list.0[0].track_name(), "Angel With A Shotgun");
+    assert_eq!(list.0[0].artist_name(), "The Cab");
+    assert_eq!(list.0[0].release_name(), Some("Symphony Soldier"));
+    assert_eq!(list.0[0].duration_ms, None);
+}
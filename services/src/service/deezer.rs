@@ -0,0 +1,135 @@
+use std::io::{
+    Cursor,
+    Read,
+};
+
+use anyhow::{
+    Context,
+    Result,
+};
+use calamine::{
+    open_workbook_from_rs,
+    Data,
+    Range,
+    RangeDeserializerBuilder,
+    Reader,
+    Xlsx,
+};
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use time::{
+    macros::{
+        date,
+        format_description,
+    },
+    Duration,
+    OffsetDateTime,
+    PrimitiveDateTime,
+};
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+/// Normalized name of the sheet containing the listening history
+const HISTORY_SHEET: &str = "listeninghistory";
+
+
+/// Represents a single row from the listening history sheet of a deezer export
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    #[serde(rename = "Date", deserialize_with = "parse_datetime")]
+    time: OffsetDateTime,
+
+    #[serde(rename = "Song Title")]
+    pub track: String,
+
+    #[serde(rename = "Artist")]
+    pub artist: String,
+
+    #[release]
+    #[serde(rename = "Album Title")]
+    pub album: Option<String>,
+
+    #[serde(rename = "ISRC")]
+    pub isrc: Option<String>,
+
+    /// Seconds the track was played for
+    #[serde(rename = "Listening Time")]
+    pub listening_time: u32,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = Info<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time.unix_timestamp() }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(Info {
+            music_service: "deezer.com",
+            isrc: self.isrc.as_deref(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Info<'i> {
+    music_service: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    isrc: Option<&'i str>,
+}
+
+
+/// Parse the listening history sheet from a deezer `.xlsx` export. Rows that fail to parse are skipped.
+pub fn parse(mut source: impl Read) -> Result<ListenVec> {
+    let mut buf = Vec::new();
+    source.read_to_end(&mut buf)?;
+
+    let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(buf))?;
+    let sheet = workbook
+        .sheet_names()
+        .into_iter()
+        .find(|name| name.to_lowercase().replace(|c: char| !c.is_alphabetic(), "").ends_with(HISTORY_SHEET))
+        .context("Listening history sheet not found")?;
+
+    parse_range(&workbook.worksheet_range(&sheet)?)
+}
+
+fn parse_range(range: &Range<Data>) -> Result<ListenVec> { Ok(RangeDeserializerBuilder::new().from_range(range)?.filter_map(Result::ok).collect()) }
+
+fn parse_datetime<'de, D>(de: D) -> Result<OffsetDateTime, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    /// Cells may be text, or a date formatted number of days since the spreadsheet epoch
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Cell {
+        Serial(f64),
+        Text(String),
+    }
+
+    match Cell::deserialize(de)? {
+        Cell::Serial(days) => Ok(date!(1899 - 12 - 30).midnight().assume_utc() + Duration::seconds((days * 86_400.0).round() as i64)),
+        Cell::Text(val) => PrimitiveDateTime::parse(&val, format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"))
+            .map(PrimitiveDateTime::assume_utc)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,84 @@
+use time::macros::datetime;
+
+use super::*;
+
+const HEADERS: [&str; 9] = [
+    "Song Title",
+    "Artist",
+    "ISRC",
+    "Album Title",
+    "IP Address",
+    "Listening Time",
+    "Platform Name",
+    "Platform Model",
+    "Date",
+];
+
+fn sample() -> Range<Data> {
+    let rows: [[Data; 9]; 3] = [
+        HEADERS.map(Data::from),
+        [
+            "Angel With A Shotgun".into(),
+            "The Cab".into(),
+            "USUM71111621".into(),
+            "Symphony Soldier".into(),
+            "0.0.0.0".into(),
+            Data::Int(203),
+            "android".into(),
+            "Pixel".into(),
+            "2022-11-24 19:32:40".into(),
+        ],
+        [
+            "Burn Brighter".into(),
+            "Lansdowne".into(),
+            Data::Empty,
+            Data::Empty,
+            "0.0.0.0".into(),
+            Data::Float(25.0),
+            "web".into(),
+            Data::Empty,
+            Data::Float(43_291.290_914_351_85),
+        ],
+    ];
+
+    let mut range = Range::new((0, 0), (2, 8));
+    for (r, row) in rows.into_iter().enumerate() {
+        for (c, cell) in row.into_iter().enumerate() {
+            range.set_value((r as u32, c as u32), cell);
+        }
+    }
+    range
+}
+
+#[test]
+fn test_de() {
+    let expected = Listen {
+        time: datetime!(2022-11-24 19:32:40 UTC),
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        album: Some("Symphony Soldier".to_owned()),
+        isrc: Some("USUM71111621".to_owned()),
+        listening_time: 203,
+    };
+
+    let list = parse_range(&sample()).expect("Failed to parse sheet");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+}
+
+#[test]
+fn test_de_serial_date() {
+    let list = parse_range(&sample()).expect("Failed to parse sheet");
+    assert_eq!(list.0[1].time, datetime!(2018-07-10 06:58:55 UTC));
+    assert_eq!(list.0[1].listening_time, 25);
+    assert_eq!(list.0[1].release_name(), None);
+}
+
+#[test]
+fn test_ser_isrc() {
+    let list = parse_range(&sample()).expect("Failed to parse sheet");
+    assert_eq!(
+        serde_json::to_value(list.0[0].track_metadata()).expect("Failed to serialize info"),
+        serde_json::json!({ "music_service": "deezer.com", "isrc": "USUM71111621" })
+    );
+}
@@ -0,0 +1,170 @@
+use std::io::Read;
+
+use anyhow::{
+    bail,
+    Context,
+    Result,
+};
+use csv::{
+    ReaderBuilder,
+    StringRecord,
+};
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::Deserialize;
+use serde_with::{
+    serde_as,
+    DisplayFromStr,
+};
+use time::UtcOffset;
+
+use super::{
+    MbidInfo,
+    TimestampFormat,
+};
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+
+/// Maps the columns of a csv file to listen fields. Columns are referenced by their header name
+#[serde_as]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Mapping {
+    pub track: String,
+    pub artist: String,
+    pub release: Option<String>,
+
+    pub timestamp: String,
+    #[serde_as(as = "DisplayFromStr")]
+    pub timestamp_format: TimestampFormat,
+
+    pub recording_mbid: Option<String>,
+    pub release_mbid: Option<String>,
+    /// Column containing one or more artist mbids separated by `,` or `;`
+    pub artist_mbids: Option<String>,
+
+    /// Field delimiter, defaults to `,`
+    pub delimiter: Option<char>,
+}
+
+impl Mapping {
+    /// Read a mapping from a json file, using the struct fields in kebab-case as keys to match the command line options
+    pub fn from_reader(source: impl Read) -> Result<Self> { serde_json::from_reader(source).context("Invalid csv mapping") }
+}
+
+/// Column indexes resolved from the headers of a file
+struct Columns {
+    track: usize,
+    artist: usize,
+    release: Option<usize>,
+    timestamp: usize,
+    recording_mbid: Option<usize>,
+    release_mbid: Option<usize>,
+    artist_mbids: Option<usize>,
+}
+
+impl Columns {
+    fn resolve(mapping: &Mapping, headers: &StringRecord) -> Result<Self> {
+        let find = |field: &str, column: &str| {
+            if column.is_empty() {
+                bail!("No column mapped for {field}");
+            }
+            headers
+                .iter()
+                .position(|h| h.trim() == column)
+                .with_context(|| format!("Column `{column}` for {field} not found"))
+        };
+        let find_opt = |field, column: &Option<String>| column.as_deref().map(|c| find(field, c)).transpose();
+
+        Ok(Self {
+            track: find("track", &mapping.track)?,
+            artist: find("artist", &mapping.artist)?,
+            release: find_opt("release", &mapping.release)?,
+            timestamp: find("timestamp", &mapping.timestamp)?,
+            recording_mbid: find_opt("recording_mbid", &mapping.recording_mbid)?,
+            release_mbid: find_opt("release_mbid", &mapping.release_mbid)?,
+            artist_mbids: find_opt("artist_mbids", &mapping.artist_mbids)?,
+        })
+    }
+}
+
+
+/// Represents a single row from a user mapped csv file
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    time: i64,
+
+    pub track: String,
+    pub artist: String,
+
+    #[release]
+    pub release: Option<String>,
+
+    pub recording_mbid: Option<String>,
+    pub release_mbid: Option<String>,
+    pub artist_mbids: Vec<String>,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = MbidInfo<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.release.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(MbidInfo::new(self.recording_mbid.as_deref(), self.release_mbid.as_deref(), self.artist_mbids.iter().map(String::as_str)))
+    }
+}
+
+impl Listen {
+    fn from_record(record: &StringRecord, columns: &Columns, mapping: &Mapping, local_offset: UtcOffset) -> Option<Self> {
+        let get = |i: usize| record.get(i).map(str::trim).filter(|v| !v.is_empty());
+        let get_opt = |i: Option<usize>| i.and_then(get).map(str::to_owned);
+
+        Some(Self {
+            time: mapping.timestamp_format.parse(get(columns.timestamp)?, local_offset).ok()?,
+            track: get(columns.track)?.to_owned(),
+            artist: get(columns.artist)?.to_owned(),
+            release: get_opt(columns.release),
+            recording_mbid: get_opt(columns.recording_mbid),
+            release_mbid: get_opt(columns.release_mbid),
+            artist_mbids: get_opt(columns.artist_mbids)
+                .map(|ids| ids.split([',', ';']).map(str::trim).filter(|id| !id.is_empty()).map(str::to_owned).collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+
+/// Parse a csv file with a header row using the columns from `mapping`. Rows that fail to parse are skipped.
+///
+/// Timestamps without an offset are converted to UTC using `local_offset`
+pub fn parse(source: impl Read, mapping: &Mapping, local_offset: UtcOffset) -> Result<ListenVec> {
+    let delimiter = match mapping.delimiter.unwrap_or(',') {
+        c if c.is_ascii() => c as u8,
+        c => bail!("Delimiter `{c}` must be an ascii character"),
+    };
+    let mut reader = ReaderBuilder::new().delimiter(delimiter).flexible(true).from_reader(source);
+    let columns = Columns::resolve(mapping, reader.headers()?)?;
+
+    Ok(reader
+        .records()
+        .filter_map(Result::ok)
+        .filter_map(|r| Listen::from_record(&r, &columns, mapping, local_offset))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,61 @@
+use time::macros::offset;
+
+use super::*;
+
+const SAMPLE: &str = "Played;Title;Artist;Album;Recording MBID;Artist MBIDs
+2022-11-24 19:32:40;Angel With A Shotgun;The Cab;Symphony Soldier;b92334c4-574a-46f5-89d8-417fcd1e873f;2ae9e8a4-6b10-4e88-a5a1-f44e4ec1ea03
+2018-07-10 06:58:55;Burn Brighter;Lansdowne;;;
+not a date;Broken;Nobody;;;
+";
+
+fn mapping() -> Mapping {
+    Mapping {
+        track: "Title".to_owned(),
+        artist: "Artist".to_owned(),
+        release: Some("Album".to_owned()),
+        timestamp: "Played".to_owned(),
+        timestamp_format: "[year]-[month]-[day] [hour]:[minute]:[second]".parse().expect("Invalid format"),
+        recording_mbid: Some("Recording MBID".to_owned()),
+        artist_mbids: Some("Artist MBIDs".to_owned()),
+        delimiter: Some(';'),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse() {
+    let expected = Listen {
+        time: 1_669_311_160,
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        release: Some("Symphony Soldier".to_owned()),
+        recording_mbid: Some("b92334c4-574a-46f5-89d8-417fcd1e873f".to_owned()),
+        release_mbid: None,
+        artist_mbids: vec!["2ae9e8a4-6b10-4e88-a5a1-f44e4ec1ea03".to_owned()],
+    };
+
+    let list = parse(SAMPLE.as_bytes(), &mapping(), offset!(+2)).expect("Failed to parse csv");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+    assert_eq!(list.0[1].release_name(), None);
+    assert!(list.0[1].artist_mbids.is_empty());
+}
+
+#[test]
+fn test_mapping_from_json() {
+    let mapping = Mapping::from_reader(r#"{"track": "name", "artist": "artist", "timestamp": "ts", "timestamp-format": "unix-ms"}"#.as_bytes())
+        .expect("Failed to read mapping");
+    let list = parse("ts,name,artist\n1669318360000,Angel With A Shotgun,The Cab\n".as_bytes(), &mapping, UtcOffset::UTC).expect("Failed to parse csv");
+    assert_eq!(list.0.len(), 1);
+    assert_eq!(list.0[0].listened_at(), 1_669_318_360);
+}
+
+#[test]
+#[should_panic(expected = "Column `Missing` for track not found")]
+fn test_parse_missing_column() {
+    let mapping = Mapping {
+        track: "Missing".to_owned(),
+        ..mapping()
+    };
+    parse(SAMPLE.as_bytes(), &mapping, UtcOffset::UTC).unwrap();
+}
@@ -0,0 +1,303 @@
+use std::{
+    fmt::Display,
+    io::Read,
+    str::FromStr,
+};
+
+use anyhow::{
+    bail,
+    Context,
+    Result,
+};
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_with::{
+    serde_as,
+    DisplayFromStr,
+};
+use time::UtcOffset;
+
+use super::{
+    MbidInfo,
+    TimestampFormat,
+};
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+
+/// Maps paths in a json document to listen fields.
+///
+/// Paths are keys separated by `.`, where `[N]` selects a single array element and `[]` selects all of them.
+/// The first `[]` selects the records to import, so it must be at the same place in every path
+#[serde_as]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Mapping {
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub track: Option<JsonPath>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub artist: Option<JsonPath>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub release: Option<JsonPath>,
+
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub timestamp: Option<JsonPath>,
+    #[serde_as(as = "DisplayFromStr")]
+    pub timestamp_format: TimestampFormat,
+
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub recording_mbid: Option<JsonPath>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub release_mbid: Option<JsonPath>,
+    /// May select a single string, or several using `[]`
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub artist_mbids: Option<JsonPath>,
+}
+
+impl Mapping {
+    /// Read a mapping from a json file, using the struct fields in kebab-case as keys to match the command line options
+    pub fn from_reader(source: impl Read) -> Result<Self> { serde_json::from_reader(source).context("Invalid json mapping") }
+}
+
+/// A path to one or more values in a json document, such as `items[].track.artists[0].name`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Each,
+}
+
+impl JsonPath {
+    /// Split the path at its first `[]`, into the path to the records and the path within each record
+    fn split_records(&self) -> (Option<&[Segment]>, &[Segment]) {
+        match self.segments.iter().position(|s| *s == Segment::Each) {
+            Some(i) => (Some(&self.segments[..=i]), &self.segments[i + 1..]),
+            None => (None, &self.segments),
+        }
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        for part in s.split('.') {
+            let (key, mut indexes) = part.split_once('[').map_or((part, ""), |(k, _)| (k, &part[k.len()..]));
+            if !key.is_empty() {
+                segments.push(Segment::Key(key.to_owned()));
+            } else if indexes.is_empty() {
+                return Err(format!("Empty key in path `{s}`"));
+            }
+            while !indexes.is_empty() {
+                let (index, rest) = indexes
+                    .strip_prefix('[')
+                    .and_then(|i| i.split_once(']'))
+                    .ok_or_else(|| format!("Invalid index in path `{s}`"))?;
+                segments.push(match index {
+                    "" => Segment::Each,
+                    i => Segment::Index(i.parse().map_err(|_| format!("Invalid index `{i}` in path `{s}`"))?),
+                });
+                indexes = rest;
+            }
+        }
+
+        Ok(Self { raw: s.to_owned(), segments })
+    }
+}
+
+impl Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(&self.raw) }
+}
+
+/// Find every value matched by `segments`
+fn select<'v>(segments: &[Segment], value: &'v Value) -> Vec<&'v Value> {
+    let Some((first, rest)) = segments.split_first() else {
+        return vec![value];
+    };
+    match (first, value) {
+        (Segment::Key(k), Value::Object(o)) => o.get(k).map(|v| select(rest, v)).unwrap_or_default(),
+        (Segment::Index(i), Value::Array(a)) => a.get(*i).map(|v| select(rest, v)).unwrap_or_default(),
+        (Segment::Each, Value::Array(a)) => a.iter().flat_map(|v| select(rest, v)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+
+/// A record that could not be imported
+#[derive(Debug)]
+pub struct RecordError {
+    pub index: usize,
+    pub path: String,
+    pub reason: String,
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "Skipping record {} at `{}`: {}", self.index, self.path, self.reason) }
+}
+
+/// The mapped paths, relative to each record
+struct Fields<'m> {
+    records: &'m [Segment],
+    track: (&'m JsonPath, &'m [Segment]),
+    artist: (&'m JsonPath, &'m [Segment]),
+    timestamp: (&'m JsonPath, &'m [Segment]),
+    release: Option<(&'m JsonPath, &'m [Segment])>,
+    recording_mbid: Option<(&'m JsonPath, &'m [Segment])>,
+    release_mbid: Option<(&'m JsonPath, &'m [Segment])>,
+    artist_mbids: Option<(&'m JsonPath, &'m [Segment])>,
+}
+
+impl<'m> Fields<'m> {
+    fn resolve(mapping: &'m Mapping) -> Result<Self> {
+        let mut records = None;
+        let mut split = |path: &'m JsonPath| {
+            let (prefix, rest) = path.split_records();
+            match records {
+                None => records = Some(prefix),
+                Some(r) if r != prefix => bail!("Path `{path}` does not select records from the same array as the other paths"),
+                _ => {},
+            }
+            Ok((path, rest))
+        };
+        macro_rules! required {
+            ($field:ident) => {
+                split(mapping.$field.as_ref().context(concat!("No path mapped for ", stringify!($field)))?)?
+            };
+        }
+        macro_rules! optional {
+            ($field:ident) => {
+                mapping.$field.as_ref().map(&mut split).transpose()?
+            };
+        }
+
+        let track = required!(track);
+        let artist = required!(artist);
+        let timestamp = required!(timestamp);
+        let release = optional!(release);
+        let recording_mbid = optional!(recording_mbid);
+        let release_mbid = optional!(release_mbid);
+        let artist_mbids = optional!(artist_mbids);
+        Ok(Self {
+            records: records.flatten().unwrap_or_default(),
+            track,
+            artist,
+            timestamp,
+            release,
+            recording_mbid,
+            release_mbid,
+            artist_mbids,
+        })
+    }
+}
+
+
+/// Represents a single record from a user mapped json file
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    time: i64,
+
+    pub track: String,
+    pub artist: String,
+
+    #[release]
+    pub release: Option<String>,
+
+    pub recording_mbid: Option<String>,
+    pub release_mbid: Option<String>,
+    pub artist_mbids: Vec<String>,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = MbidInfo<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.release.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(MbidInfo::new(self.recording_mbid.as_deref(), self.release_mbid.as_deref(), self.artist_mbids.iter().map(String::as_str)))
+    }
+}
+
+impl Listen {
+    fn from_record(index: usize, record: &Value, fields: &Fields, format: &TimestampFormat, local_offset: UtcOffset) -> Result<Self, RecordError> {
+        let error = |path: &JsonPath, reason: String| RecordError {
+            index,
+            path: path.to_string(),
+            reason,
+        };
+        let strings = |(_, segments): (&JsonPath, &[Segment])| {
+            select(segments, record)
+                .into_iter()
+                .filter_map(|v| match v {
+                    Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_owned()),
+                    Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let required = |field: (&JsonPath, &[Segment])| {
+            strings(field)
+                .into_iter()
+                .next()
+                .ok_or_else(|| error(field.0, "Missing or not a string".to_owned()))
+        };
+        let optional = |field: Option<(&JsonPath, &[Segment])>| field.and_then(|f| strings(f).into_iter().next());
+
+        Ok(Self {
+            time: format
+                .parse(&required(fields.timestamp)?, local_offset)
+                .map_err(|e| error(fields.timestamp.0, e))?,
+            track: required(fields.track)?,
+            artist: required(fields.artist)?,
+            release: optional(fields.release),
+            recording_mbid: optional(fields.recording_mbid),
+            release_mbid: optional(fields.release_mbid),
+            artist_mbids: fields.artist_mbids.map(strings).unwrap_or_default(),
+        })
+    }
+}
+
+
+/// Parse a json document using the paths from `mapping`.
+/// Records that fail to parse are skipped, and returned as errors alongside the listens
+///
+/// Timestamps without an offset are converted to UTC using `local_offset`
+pub fn parse(source: impl Read, mapping: &Mapping, local_offset: UtcOffset) -> Result<(ListenVec, Vec<RecordError>)> {
+    let fields = Fields::resolve(mapping)?;
+    let root: Value = serde_json::from_reader(source)?;
+
+    let mut errors = Vec::new();
+    let listens = select(fields.records, &root)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, record)| {
+            Listen::from_record(i, record, &fields, &mapping.timestamp_format, local_offset)
+                .map_err(|e| errors.push(e))
+                .ok()
+        })
+        .collect();
+    Ok((listens, errors))
+}
+
+#[cfg(test)]
+mod tests;
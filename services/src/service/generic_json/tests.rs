@@ -0,0 +1,97 @@
+use super::*;
+
+const SAMPLE: &str = r#"{
+    "items": [
+        {
+            "played_at": "2022-11-24T19:32:40Z",
+            "track": {
+                "name": "Angel With A Shotgun",
+                "album": { "name": "Symphony Soldier" },
+                "artists": [
+                    { "name": "The Cab", "mbid": "2ae9e8a4-6b10-4e88-a5a1-f44e4ec1ea03" },
+                    { "name": "Someone Else", "mbid": "00000000-0000-0000-0000-000000000000" }
+                ]
+            }
+        },
+        {
+            "played_at": 1531205935,
+            "track": { "name": "Burn Brighter", "artists": [{ "name": "Lansdowne" }] }
+        },
+        {
+            "played_at": "2022-11-24T19:32:40Z",
+            "track": { "name": "Missing Artist", "artists": [] }
+        }
+    ]
+}"#;
+
+fn mapping() -> Mapping {
+    Mapping {
+        track: "items[].track.name".parse().ok(),
+        artist: "items[].track.artists[0].name".parse().ok(),
+        release: "items[].track.album.name".parse().ok(),
+        timestamp: "items[].played_at".parse().ok(),
+        timestamp_format: TimestampFormat::Rfc3339,
+        artist_mbids: "items[].track.artists[].mbid".parse().ok(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse() {
+    let expected = Listen {
+        time: 1_669_318_360,
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        release: Some("Symphony Soldier".to_owned()),
+        recording_mbid: None,
+        release_mbid: None,
+        artist_mbids: vec![
+            "2ae9e8a4-6b10-4e88-a5a1-f44e4ec1ea03".to_owned(),
+            "00000000-0000-0000-0000-000000000000".to_owned(),
+        ],
+    };
+
+    let (list, errors) = parse(SAMPLE.as_bytes(), &mapping(), UtcOffset::UTC).expect("Failed to parse json");
+    assert_eq!(list.0.len(), 1);
+    assert_eq!(list.0[0], expected);
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].index, 1);
+    assert_eq!(errors[0].path, "items[].played_at");
+    assert_eq!(errors[1].index, 2);
+    assert_eq!(errors[1].to_string(), "Skipping record 2 at `items[].track.artists[0].name`: Missing or not a string");
+}
+
+#[test]
+fn test_parse_root_array() {
+    let mapping = Mapping::from_reader(r#"{"track": "[].t", "artist": "[].a", "timestamp": "[].ts"}"#.as_bytes()).expect("Failed to read mapping");
+    let (list, errors) =
+        parse(r#"[{"t": "Burn Brighter", "a": "Lansdowne", "ts": 1531205935}]"#.as_bytes(), &mapping, UtcOffset::UTC).expect("Failed to parse json");
+    assert!(errors.is_empty());
+    assert_eq!(list.0[0].listened_at(), 1_531_205_935);
+}
+
+#[test]
+fn test_path_parse() {
+    let path: JsonPath = "items[].track.artists[0].name".parse().expect("Failed to parse path");
+    assert_eq!(path.segments, [
+        Segment::Key("items".to_owned()),
+        Segment::Each,
+        Segment::Key("track".to_owned()),
+        Segment::Key("artists".to_owned()),
+        Segment::Index(0),
+        Segment::Key("name".to_owned()),
+    ]);
+    assert!("items..name".parse::<JsonPath>().is_err());
+    assert!("items[x]".parse::<JsonPath>().is_err());
+}
+
+#[test]
+#[should_panic(expected = "does not select records from the same array")]
+fn test_mismatched_records() {
+    let mapping = Mapping {
+        artist: "other[].artist".parse().ok(),
+        ..mapping()
+    };
+    parse(SAMPLE.as_bytes(), &mapping, UtcOffset::UTC).unwrap();
+}
@@ -0,0 +1,152 @@
+use std::{
+    io::Read,
+    path::Path,
+};
+
+use anyhow::Result;
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use rusqlite::Connection;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use time::{
+    format_description::FormatItem,
+    macros::format_description,
+    OffsetDateTime,
+    PrimitiveDateTime,
+    UtcOffset,
+};
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+const QUERY: &str = "SELECT DateCreated, UserId, ItemName, PlayDuration FROM PlaybackActivity WHERE ItemType = 'Audio'";
+
+
+/// Represents a single audio play recorded by the jellyfin playback reporting plugin
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+#[serde(try_from = "Activity")]
+pub struct Listen {
+    time: OffsetDateTime,
+
+    pub user_id: String,
+
+    pub track: String,
+    pub artist: String,
+
+    #[release]
+    pub album: Option<String>,
+
+    /// Seconds the track was played for
+    pub play_duration: u32,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = Info;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time.unix_timestamp() }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> { Some(Info { media_player: "Jellyfin" }) }
+}
+
+#[derive(Serialize)]
+pub struct Info {
+    media_player: &'static str,
+}
+
+
+/// A row from the `PlaybackActivity` table
+#[derive(Deserialize)]
+struct Activity {
+    #[serde(rename = "DateCreated")]
+    date_created: String,
+    #[serde(rename = "UserId")]
+    user_id: String,
+    #[serde(rename = "ItemName")]
+    item_name: String,
+    #[serde(rename = "PlayDuration")]
+    play_duration: u32,
+}
+
+impl TryFrom<Activity> for Listen {
+    type Error = String;
+
+    fn try_from(activity: Activity) -> Result<Self, Self::Error> {
+        const FMTS: &[&[FormatItem]] = &[
+            format_description!("[year]-[month]-[day] [hour]:[minute]:[second].[subsecond]"),
+            format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+        ];
+
+        let time = FMTS
+            .iter()
+            .find_map(|fmt| PrimitiveDateTime::parse(&activity.date_created, fmt).ok())
+            .ok_or_else(|| format!("Invalid date: {}", activity.date_created))?
+            .assume_utc();
+
+        // Audio items are named `{artist} - {track} ({album})`
+        let (artist, track) = activity
+            .item_name
+            .split_once(" - ")
+            .ok_or_else(|| format!("Missing artist: {}", activity.item_name))?;
+        let (track, album) = track
+            .strip_suffix(')')
+            .and_then(|t| t.rsplit_once(" ("))
+            .map_or((track, None), |(t, a)| (t, Some(a.to_owned())));
+
+        Ok(Self {
+            time,
+            user_id: activity.user_id,
+            track: track.to_owned(),
+            artist: artist.to_owned(),
+            album,
+            play_duration: activity.play_duration,
+        })
+    }
+}
+
+
+/// Read all audio plays from a `playback_reporting.db`. Rows that fail to parse are skipped.
+///
+/// The plugin records the local time of the server, so `local_offset` is used to convert those to UTC.
+/// Only the main database file can be read from `source`, so plays still in its `-wal` file are missed,
+/// use [`parse_file`] to include those
+pub fn parse(source: impl Read, local_offset: UtcOffset) -> Result<ListenVec> { read(&super::open_sqlite(source)?, local_offset) }
+
+/// Same as [`parse`], but reads the database in place, including any plays still in its `-wal` file
+pub fn parse_file(path: &Path, local_offset: UtcOffset) -> Result<ListenVec> { read(&super::open_sqlite_file(path)?, local_offset) }
+
+fn read(conn: &Connection, local_offset: UtcOffset) -> Result<ListenVec> {
+    let mut stmt = conn.prepare(QUERY)?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Activity {
+            date_created: row.get(0)?,
+            user_id: row.get(1)?,
+            item_name: row.get(2)?,
+            play_duration: row.get(3)?,
+        })
+    })?;
+
+    Ok(rows
+        .filter_map(|r| Listen::try_from(r.ok()?).ok())
+        .map(|l| Listen {
+            time: l.time.replace_offset(local_offset),
+            ..l
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests;
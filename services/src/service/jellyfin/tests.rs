@@ -0,0 +1,76 @@
+use rusqlite::{
+    Connection,
+    MAIN_DB,
+};
+use time::macros::{
+    datetime,
+    offset,
+};
+
+use super::*;
+
+fn sample_db() -> Vec<u8> {
+    let conn = Connection::open_in_memory().expect("Failed to open database");
+    conn.execute_batch(
+        "CREATE TABLE PlaybackActivity (DateCreated DATETIME NOT NULL, UserId TEXT, ItemId TEXT, ItemType TEXT, ItemName TEXT, PlaybackMethod TEXT, ClientName TEXT, DeviceName TEXT, PlayDuration INT);
+        INSERT INTO PlaybackActivity VALUES
+            ('2022-11-24 19:32:40.6170000', 'a1b2c3d4e5f60718293a4b5c6d7e8f90', '1', 'Audio', 'The Cab - Angel With A Shotgun (Symphony Soldier)', 'DirectPlay', 'Web', 'Firefox', 203),
+            ('2022-11-24 19:40:00.0000000', 'a1b2c3d4e5f60718293a4b5c6d7e8f90', '2', 'Movie', 'Some Movie', 'DirectPlay', 'Web', 'Firefox', 5400),
+            ('2018-07-10 06:58:55', '0f0e0d0c0b0a09080706050403020100', '3', 'Audio', 'Lansdowne - Burn Brighter', 'DirectPlay', 'Web', 'Firefox', 25),
+            ('2018-07-10 07:00:00', '0f0e0d0c0b0a09080706050403020100', '4', 'Audio', 'No Artist', 'DirectPlay', 'Web', 'Firefox', 25);",
+    )
+    .expect("Failed to create sample data");
+    conn.serialize(MAIN_DB).expect("Failed to serialize database").to_vec()
+}
+
+#[test]
+fn test_parse() {
+    let expected = Listen {
+        time: datetime!(2022-11-24 19:32:40.617 +2),
+        user_id: "a1b2c3d4e5f60718293a4b5c6d7e8f90".to_owned(),
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        album: Some("Symphony Soldier".to_owned()),
+        play_duration: 203,
+    };
+
+    let list = parse(sample_db().as_slice(), offset!(+2)).expect("Failed to read database");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+    assert_eq!(list.0[0].listened_at(), datetime!(2022-11-24 17:32:40 UTC).unix_timestamp());
+}
+
+#[test]
+fn test_parse_no_album() {
+    let list = parse(sample_db().as_slice(), UtcOffset::UTC).expect("Failed to read database");
+    assert_eq!(list.0[1].track_name(), "Burn Brighter");
+    assert_eq!(list.0[1].artist_name(), "Lansdowne");
+    assert_eq!(list.0[1].release_name(), None);
+}
+
+#[test]
+fn test_parse_file_wal() {
+    let dir = std::env::temp_dir().join(format!("lb-importer-jellyfin-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Failed to create directory");
+    let path = dir.join("playback_reporting.db");
+    std::fs::write(&path, sample_db()).expect("Failed to write database");
+
+    // Keep the writer open without checkpointing, the same as a running server
+    let writer = Connection::open(&path).expect("Failed to open database");
+    writer
+        .execute_batch(
+            "PRAGMA journal_mode=WAL; PRAGMA wal_autocheckpoint=0;
+            INSERT INTO PlaybackActivity VALUES ('2023-01-01 12:00:00', 'a1b2c3d4e5f60718293a4b5c6d7e8f90', '5', 'Audio', 'Lansdowne - Blue Collar Revolution (Blue Collar Revolution)', 'DirectPlay', 'Web', 'Firefox', 200);",
+        )
+        .expect("Failed to write to database");
+
+    let list = parse_file(&path, UtcOffset::UTC).expect("Failed to read database");
+    assert_eq!(list.0.len(), 3);
+    assert_eq!(list.0[2].track_name(), "Blue Collar Revolution");
+
+    let copy = parse(std::fs::read(&path).expect("Failed to read file").as_slice(), UtcOffset::UTC).expect("Failed to read database");
+    assert_eq!(copy.0.len(), 2);
+
+    drop(writer);
+    std::fs::remove_dir_all(dir).expect("Failed to clean up");
+}
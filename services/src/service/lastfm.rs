@@ -0,0 +1,126 @@
+use std::io::Read;
+
+use csv::{
+    ReaderBuilder,
+    StringRecord,
+};
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::Deserialize;
+use time::{
+    format_description::{
+        well_known::Rfc3339,
+        FormatItem,
+    },
+    macros::format_description,
+    OffsetDateTime,
+    PrimitiveDateTime,
+};
+
+use super::MbidInfo;
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+/// Column names used for exports that don't include a header row
+const DEFAULT_HEADERS: [&str; 4] = ["artist", "album", "track", "date"];
+
+
+/// Represents a single scrobble from a last.fm csv export
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    #[serde(rename = "date", deserialize_with = "parse_datetime")]
+    time: OffsetDateTime,
+
+    pub track: String,
+    pub track_mbid: Option<String>,
+
+    pub artist: String,
+    pub artist_mbid: Option<String>,
+
+    #[release]
+    pub album: Option<String>,
+    pub album_mbid: Option<String>,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = MbidInfo<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time.unix_timestamp() }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(MbidInfo::new(self.track_mbid.as_deref(), self.album_mbid.as_deref(), self.artist_mbid.as_deref()))
+    }
+}
+
+
+/// Parse a last.fm csv export, with or without a header row. Rows that fail to parse are skipped.
+///
+/// Exports without headers are expected to have the columns `artist, album, track, date`
+pub fn parse(source: impl Read) -> csv::Result<ListenVec> {
+    let mut reader = ReaderBuilder::new().has_headers(false).flexible(true).from_reader(source);
+    let mut records = reader.records();
+
+    let first = records.next().transpose()?.unwrap_or_default();
+    let (headers, first) = if first.iter().any(|h| h.eq_ignore_ascii_case("artist")) && first.iter().any(|h| h.eq_ignore_ascii_case("track")) {
+        (normalize_headers(&first), None)
+    } else {
+        (StringRecord::from(DEFAULT_HEADERS.as_slice()), Some(first))
+    };
+
+    Ok(first
+        .into_iter()
+        .map(Ok)
+        .chain(records)
+        .filter_map(|r| r.ok()?.deserialize(Some(&headers)).ok())
+        .collect())
+}
+
+/// Lowercase all headers and choose the most precise of the available date columns
+fn normalize_headers(headers: &StringRecord) -> StringRecord {
+    let has_uts = headers.iter().any(|h| h.eq_ignore_ascii_case("uts"));
+    headers
+        .iter()
+        .map(str::to_ascii_lowercase)
+        .map(|h| match h.as_str() {
+            "uts" => "date".to_owned(),
+            "utc_time" if !has_uts => "date".to_owned(),
+            _ => h,
+        })
+        .collect()
+}
+
+fn parse_datetime<'de, D>(de: D) -> Result<OffsetDateTime, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    const FMTS: &[&[FormatItem]] = &[
+        format_description!("[day padding:none] [month repr:short] [year], [hour]:[minute]"),
+        format_description!("[day padding:none] [month repr:short] [year] [hour]:[minute]"),
+    ];
+
+    let val = String::deserialize(de)?;
+    if let Ok(ts) = val.parse() {
+        return OffsetDateTime::from_unix_timestamp(ts).map_err(serde::de::Error::custom);
+    }
+    OffsetDateTime::parse(&val, &Rfc3339).or_else(|e| {
+        FMTS.iter()
+            .find_map(|fmt| PrimitiveDateTime::parse(&val, fmt).ok())
+            .map(PrimitiveDateTime::assume_utc)
+            .ok_or_else(|| serde::de::Error::custom(e))
+    })
+}
+
+#[cfg(test)]
+mod tests;
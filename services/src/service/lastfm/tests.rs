@@ -0,0 +1,65 @@
+use time::macros::datetime;
+
+use super::*;
+
+const HEADER_SAMPLE: &str = "\
+uts,utc_time,artist,artist_mbid,album,album_mbid,track,track_mbid
+1669318360,\"24 Nov 2022, 19:32\",The Cab,91f7a868-d82e-4cfb-9cd9-a2ffd7faac25,Symphony Soldier,,Angel With A Shotgun,b92334c4-574a-46f5-89d8-417fcd1e873f
+,,missing,,date,,row,
+1531205935,\"10 Jul 2018, 06:58\",Lansdowne,,,,Burn Brighter,
+";
+
+const SIMPLE_SAMPLE: &str = "\
+The Cab,Symphony Soldier,Angel With A Shotgun,24 Nov 2022 19:32
+Lansdowne,,Burn Brighter,not a date
+Lansdowne,,Burn Brighter,10 Jul 2018 06:58
+";
+
+#[test]
+fn test_de_headers() {
+    let expected = Listen {
+        time: datetime!(2022-11-24 19:32:40 UTC),
+        track: "Angel With A Shotgun".to_owned(),
+        track_mbid: Some("b92334c4-574a-46f5-89d8-417fcd1e873f".to_owned()),
+        artist: "The Cab".to_owned(),
+        artist_mbid: Some("91f7a868-d82e-4cfb-9cd9-a2ffd7faac25".to_owned()),
+        album: Some("Symphony Soldier".to_owned()),
+        album_mbid: None,
+    };
+
+    let list = parse(HEADER_SAMPLE.as_bytes()).expect("Failed to parse csv");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+    assert_eq!(list.0[1].album, None);
+}
+
+#[test]
+fn test_de_simple() {
+    let expected = Listen {
+        time: datetime!(2022-11-24 19:32 UTC),
+        track: "Angel With A Shotgun".to_owned(),
+        track_mbid: None,
+        artist: "The Cab".to_owned(),
+        artist_mbid: None,
+        album: Some("Symphony Soldier".to_owned()),
+        album_mbid: None,
+    };
+
+    let list = parse(SIMPLE_SAMPLE.as_bytes()).expect("Failed to parse csv");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+    assert_eq!(list.0[1].listened_at(), datetime!(2018-07-10 06:58 UTC).unix_timestamp());
+}
+
+#[test]
+fn test_ser_mbids() {
+    let list = parse(HEADER_SAMPLE.as_bytes()).expect("Failed to parse csv");
+    let info = serde_json::to_value(list.0[0].track_metadata()).expect("Failed to serialize info");
+    assert_eq!(
+        info,
+        serde_json::json!({
+            "recording_mbid": "b92334c4-574a-46f5-89d8-417fcd1e873f",
+            "artist_mbids": ["91f7a868-d82e-4cfb-9cd9-a2ffd7faac25"],
+        })
+    );
+}
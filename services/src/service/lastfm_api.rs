@@ -0,0 +1,157 @@
+use std::io::Read;
+
+use anyhow::Context;
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::{
+    de::Error,
+    Deserialize,
+    Deserializer,
+};
+use serde_with::{
+    serde_as,
+    DeserializeAs,
+    DisplayFromStr,
+    NoneAsEmptyString,
+    Same,
+    VecSkipError,
+};
+
+use super::MbidInfo;
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+
+/// Represents a single track from a saved `user.getRecentTracks` response page
+#[serde_as]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+#[payload(artist = artist_info.name: String)]
+#[payload(release = album.name: String)]
+pub struct Listen {
+    #[serde(rename = "name")]
+    pub track: String,
+    #[serde_as(as = "NoneAsEmptyString")]
+    #[serde(default)]
+    pub mbid: Option<String>,
+
+    #[serde(rename = "artist")]
+    pub artist_info: Artist,
+    pub album: Album,
+
+    date: Date,
+
+    #[serde(rename = "@attr", default)]
+    attr: Attr,
+}
+
+#[serde_as]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize)]
+pub struct Artist {
+    #[serde(rename = "#text", alias = "name")]
+    pub name: String,
+    #[serde_as(as = "NoneAsEmptyString")]
+    #[serde(default)]
+    pub mbid: Option<String>,
+}
+
+#[serde_as]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize)]
+pub struct Album {
+    #[serde_as(as = "NoneAsEmptyString")]
+    #[serde(rename = "#text", alias = "name", default)]
+    pub name: Option<String>,
+    #[serde_as(as = "NoneAsEmptyString")]
+    #[serde(default)]
+    pub mbid: Option<String>,
+}
+
+#[serde_as]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize)]
+struct Date {
+    #[serde_as(as = "DisplayFromStr")]
+    uts: i64,
+}
+
+#[serde_as]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Default, Deserialize)]
+struct Attr {
+    #[serde_as(as = "DisplayFromStr")]
+    #[serde(default)]
+    nowplaying: bool,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = MbidInfo<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.date.uts }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist_info.name.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.name.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(MbidInfo::new(self.mbid.as_deref(), self.album.mbid.as_deref(), self.artist_info.mbid.as_deref()))
+    }
+}
+
+
+/// A saved file may contain a single response page, a list of pages, or just the track list
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Dump {
+    Page(Page),
+    #[serde(deserialize_with = "parse_some")]
+    Pages(Vec<Page>),
+    #[serde(deserialize_with = "parse_some")]
+    Tracks(Vec<Listen>),
+}
+
+#[derive(Deserialize)]
+struct Page {
+    recenttracks: RecentTracks,
+}
+
+#[derive(Deserialize)]
+struct RecentTracks {
+    track: ListenVec,
+}
+
+/// Skips any items that fail to parse, but fails if none of them could be read so that lists of pages, tracks, or
+/// anything else aren't mistaken for each other
+fn parse_some<'de, D, T>(de: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let items: Vec<T> = VecSkipError::<Same>::deserialize_as(de)?;
+    if items.is_empty() {
+        return Err(D::Error::custom("no readable items"));
+    }
+    Ok(items)
+}
+
+/// Parse a saved `user.getRecentTracks` dump, skipping the currently playing track since it isn't a complete listen
+pub fn parse(source: impl Read) -> anyhow::Result<ListenVec> {
+    let dump = serde_json::from_reader(source).context("No recent tracks pages or tracks found")?;
+    let tracks = match dump {
+        Dump::Page(p) => p.recenttracks.track.0,
+        Dump::Pages(pages) => pages.into_iter().flat_map(|p| p.recenttracks.track).collect(),
+        Dump::Tracks(t) => t,
+    };
+    Ok(tracks.into_iter().filter(|l| !l.attr.nowplaying).collect())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,115 @@
+use super::*;
+
+const NOW_PLAYING: &str = r##"{
+    "artist": { "mbid": "", "#text": "Lansdowne" },
+    "streamable": "0",
+    "image": [],
+    "mbid": "",
+    "album": { "mbid": "", "#text": "No Home but the Road" },
+    "name": "Burn Brighter",
+    "@attr": { "nowplaying": "true" },
+    "url": "https://www.last.fm/music/Lansdowne/_/Burn+Brighter"
+}"##;
+
+const TRACK: &str = r##"{
+    "artist": { "mbid": "91f7a868-d82e-4cfb-9cd9-a2ffd7faac25", "#text": "The Cab" },
+    "streamable": "0",
+    "image": [],
+    "mbid": "b92334c4-574a-46f5-89d8-417fcd1e873f",
+    "album": { "mbid": "", "#text": "Symphony Soldier" },
+    "name": "Angel With A Shotgun",
+    "url": "https://www.last.fm/music/The+Cab/_/Angel+With+A+Shotgun",
+    "date": { "uts": "1669318360", "#text": "24 Nov 2022, 19:32" }
+}"##;
+
+const EXTENDED_TRACK: &str = r##"{
+    "artist": { "url": "https://www.last.fm/music/Lansdowne", "name": "Lansdowne", "image": [], "mbid": "" },
+    "mbid": "",
+    "album": { "mbid": "", "#text": "" },
+    "streamable": "0",
+    "url": "https://www.last.fm/music/Lansdowne/_/Burn+Brighter",
+    "name": "Burn Brighter",
+    "image": [],
+    "loved": "0",
+    "date": { "uts": "1531205935", "#text": "10 Jul 2018, 06:58" }
+}"##;
+
+macro_rules! PAGE_SAMPLE {
+    () => {
+        format!(r#"{{"recenttracks": {{"track": [{NOW_PLAYING},{TRACK},{{}},{EXTENDED_TRACK}], "@attr": {{"user": "test", "page": "1", "total": "2"}}}}}}"#)
+    };
+}
+
+#[test]
+fn test_de() {
+    let expected = Listen {
+        track: "Angel With A Shotgun".to_owned(),
+        mbid: Some("b92334c4-574a-46f5-89d8-417fcd1e873f".to_owned()),
+        artist_info: Artist {
+            name: "The Cab".to_owned(),
+            mbid: Some("91f7a868-d82e-4cfb-9cd9-a2ffd7faac25".to_owned()),
+        },
+        album: Album {
+            name: Some("Symphony Soldier".to_owned()),
+            mbid: None,
+        },
+        date: Date { uts: 1_669_318_360 },
+        attr: Attr::default(),
+    };
+
+    let listen: Listen = serde_json::from_str(TRACK).expect("Failed to parse track");
+    assert_eq!(listen, expected);
+}
+
+#[test]
+fn test_de_extended() {
+    let listen: Listen = serde_json::from_str(EXTENDED_TRACK).expect("Failed to parse extended track");
+    assert_eq!(listen.artist_name(), "Lansdowne");
+    assert_eq!(listen.release_name(), None);
+    assert_eq!(listen.listened_at(), 1_531_205_935);
+}
+
+#[test]
+fn test_parse_pages() {
+    let page = PAGE_SAMPLE!();
+    let single = parse(page.as_bytes()).expect("Failed to parse page");
+    assert_eq!(single.0.len(), 2);
+    assert!(single.0.iter().all(|l| !l.attr.nowplaying));
+
+    let multiple = parse(format!("[{page},{page}]").as_bytes()).expect("Failed to parse pages");
+    assert_eq!(multiple.0.len(), 4);
+
+    let error = r#"{"error": 8, "message": "Operation failed - Most likely the backend service failed. Please try again."}"#;
+    let with_error = parse(format!("[{page},{error},{page}]").as_bytes()).expect("Failed to parse pages");
+    assert_eq!(with_error.0.len(), 4);
+}
+
+#[test]
+fn test_parse_nothing_read() {
+    let error = r#"{"error": 8, "message": "Operation failed"}"#;
+    assert!(parse(error.as_bytes()).is_err());
+    assert!(parse(format!("[{error},{error}]").as_bytes()).is_err());
+    assert!(parse(r#"[{"unrelated": true}, 1, "two"]"#.as_bytes()).is_err());
+    assert!(parse("[]".as_bytes()).is_err());
+}
+
+#[test]
+fn test_skip_now_playing() {
+    let dated = NOW_PLAYING.replace(r#""@attr""#, r#""date": { "uts": "1669318360" }, "@attr""#);
+    let list = parse(format!("[{dated},{TRACK}]").as_bytes()).expect("Failed to parse tracks");
+    assert_eq!(list.0.len(), 1);
+    assert_eq!(list.0[0].track, "Angel With A Shotgun");
+}
+
+#[test]
+fn test_ser_mbids() {
+    let listen: Listen = serde_json::from_str(TRACK).expect("Failed to parse track");
+    let info = serde_json::to_value(listen.track_metadata()).expect("Failed to serialize info");
+    assert_eq!(
+        info,
+        serde_json::json!({
+            "recording_mbid": "b92334c4-574a-46f5-89d8-417fcd1e873f",
+            "artist_mbids": ["91f7a868-d82e-4cfb-9cd9-a2ffd7faac25"],
+        })
+    );
+}
@@ -1,3 +1,11 @@
+use std::io::{
+    BufRead,
+    BufReader,
+    Cursor,
+    Read,
+};
+
+use anyhow::Result;
 use lb_importer_core::ListenData;
 use lb_importer_derive::IntoPayload;
 use listenbrainz::raw::response::UserListensTrackMetadata;
@@ -6,9 +14,16 @@ use serde::{
     Deserialize,
     Serialize,
 };
+use time::{
+    format_description::well_known::Rfc3339,
+    OffsetDateTime,
+};
+use zip::ZipArchive;
 
 pub type ListenVec = super::ListenVec<Listen>;
 
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
 #[cfg_attr(test, derive(PartialEq, Eq))]
 #[derive(Debug, Deserialize, IntoPayload)]
 #[payload(track = track_metadata.data.track_name: String)]
@@ -16,6 +31,7 @@ pub type ListenVec = super::ListenVec<Listen>;
 #[payload(release = track_metadata.data.release_name: String)]
 pub struct Listen {
     track_metadata: AdditionalInfo,
+    #[serde(deserialize_with = "parse_timestamp")]
     listened_at: i64,
 }
 
@@ -63,5 +79,77 @@ impl ListenData for Listen {
 }
 
 
+/// Parse any of the listenbrainz export formats. Listens that fail to parse are skipped.
+///
+/// * A single json array of listens
+/// * Json lines with one listen per line
+/// * A zip archive containing `listens/YYYY/M.jsonl` files
+pub fn parse(source: impl Read) -> Result<ListenVec> {
+    let mut source = BufReader::new(source);
+    let start = source.fill_buf()?;
+    if start.starts_with(ZIP_MAGIC) {
+        parse_zip(source)
+    } else if start.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[') {
+        serde_json::from_reader(source).map_err(Into::into)
+    } else {
+        parse_lines(source)
+    }
+}
+
+fn parse_lines(source: impl BufRead) -> Result<ListenVec> {
+    let mut listens = Vec::new();
+    for line in source.lines() {
+        if let Ok(listen) = serde_json::from_str(&line?) {
+            listens.push(listen);
+        }
+    }
+    Ok(listens.into_iter().collect())
+}
+
+fn parse_zip(mut source: impl Read) -> Result<ListenVec> {
+    let mut buf = Vec::new();
+    source.read_to_end(&mut buf)?;
+
+    let mut archive = ZipArchive::new(Cursor::new(buf))?;
+    let mut listens = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        if is_listens_file(file.name()) {
+            listens.extend(parse_lines(BufReader::new(file))?);
+        }
+    }
+    Ok(listens.into_iter().collect())
+}
+
+/// Matches `listens/YYYY/M.jsonl`, optionally nested in a top level directory
+fn is_listens_file(name: &str) -> bool {
+    let mut parts = name.rsplit('/');
+    matches!(
+        (parts.next().and_then(|f| f.strip_suffix(".jsonl")), parts.next(), parts.next()),
+        (Some(month), Some(year), Some("listens")) if [month, year].iter().all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+    )
+}
+
+/// Older exports use a unix timestamp, but allow an RFC 3339 string as well
+fn parse_timestamp<'de, D>(de: D) -> Result<i64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Unix(i64),
+        Text(String),
+    }
+
+    match Timestamp::deserialize(de)? {
+        Timestamp::Unix(ts) => Ok(ts),
+        Timestamp::Text(dt) => OffsetDateTime::parse(&dt, &Rfc3339)
+            .map(OffsetDateTime::unix_timestamp)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+
 #[cfg(test)]
 mod tests;
@@ -87,3 +87,60 @@ impl PartialEq for AdditionalInfo {
     }
 }
 impl Eq for AdditionalInfo {}
+
+#[test]
+fn test_parse_lines() {
+    let lines = format!("{}\n{{}}\n\n{}\n", SAMPLE.replace('\n', ""), SAMPLE.replace('\n', ""));
+    let list = parse(lines.as_bytes()).expect("Failed to parse json lines");
+    assert_eq!(list.0.len(), 2);
+}
+
+#[test]
+fn test_parse_array() {
+    let list = parse(format!(" [{SAMPLE},{{}}]").as_bytes()).expect("Failed to parse json array");
+    assert_eq!(list.0.len(), 1);
+}
+
+#[test]
+fn test_parse_zip() {
+    use std::io::Write;
+
+    use zip::{
+        write::SimpleFileOptions,
+        CompressionMethod,
+        ZipWriter,
+    };
+
+    let line = SAMPLE.replace('\n', "");
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    for (name, content) in [
+        ("user.json", "{}".to_owned()),
+        ("feedback.jsonl", line.clone()),
+        ("listens/2022/11.jsonl", format!("{line}\n{line}")),
+        ("listens/2023/1.jsonl", line.clone()),
+    ] {
+        zip.start_file(name, options).expect("Failed to start zip entry");
+        zip.write_all(content.as_bytes()).expect("Failed to write zip entry");
+    }
+    let buf = zip.finish().expect("Failed to finish zip").into_inner();
+
+    let list = parse(buf.as_slice()).expect("Failed to parse zip");
+    assert_eq!(list.0.len(), 3);
+}
+
+#[test]
+fn test_listens_file_names() {
+    assert!(is_listens_file("listens/2022/11.jsonl"));
+    assert!(is_listens_file("export/listens/2023/1.jsonl"));
+    assert!(!is_listens_file("feedback.jsonl"));
+    assert!(!is_listens_file("listens/2022/11.json"));
+    assert!(!is_listens_file("other/2022/11.jsonl"));
+}
+
+#[test]
+fn test_de_rfc3339_timestamp() {
+    let sample = SAMPLE.replace("1669318360", r#""2022-11-24T19:32:40+00:00""#);
+    let listen: Listen = serde_json::from_str(&sample).expect("Failed to parse listen");
+    assert_eq!(listen.listened_at(), 1_669_318_360);
+}
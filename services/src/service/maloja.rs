@@ -0,0 +1,115 @@
+use std::io::Read;
+
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+const ARTIST_SEPARATOR: &str = ", ";
+
+
+/// Represents a single scrobble from a maloja backup
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+#[serde(from = "Scrobble")]
+pub struct Listen {
+    time: i64,
+
+    pub track: String,
+
+    /// All artists joined into a single name
+    pub artist: String,
+    pub artists: Vec<String>,
+
+    #[release]
+    pub album: Option<String>,
+
+    /// Seconds the track was played for
+    pub duration: Option<u32>,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = Info<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(Info {
+            artist_names: self.artists.as_slice(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Info<'i> {
+    artist_names: &'i [String],
+}
+
+
+#[derive(Deserialize)]
+struct Scrobble {
+    time: i64,
+    track: Track,
+    duration: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct Track {
+    artists: Vec<String>,
+    title: String,
+    album: Option<Album>,
+}
+
+/// Newer backups store album details while older ones only have the title
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Album {
+    Title(String),
+    Details { albumtitle: String },
+}
+
+impl From<Scrobble> for Listen {
+    fn from(scrobble: Scrobble) -> Self {
+        let Scrobble {
+            time,
+            track: Track { artists, title, album },
+            duration,
+        } = scrobble;
+        Self {
+            time,
+            track: title,
+            artist: artists.join(ARTIST_SEPARATOR),
+            artists,
+            album: album.map(|a| match a {
+                Album::Title(t) | Album::Details { albumtitle: t } => t,
+            }),
+            duration,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Backup {
+    scrobbles: ListenVec,
+}
+
+/// Parse a `maloja_export_*.json` backup
+pub fn parse(source: impl Read) -> serde_json::Result<ListenVec> { serde_json::from_reader::<_, Backup>(source).map(|b| b.scrobbles) }
+
+#[cfg(test)]
+mod tests;
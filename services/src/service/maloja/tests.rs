@@ -0,0 +1,64 @@
+use super::*;
+
+const SAMPLE: &str = r#"{
+    "maloja": { "export_time": 1669320000 },
+    "scrobbles": [
+        {
+            "time": 1669318360,
+            "track": {
+                "artists": ["The Cab"],
+                "title": "Angel With A Shotgun",
+                "album": { "albumtitle": "Symphony Soldier", "artists": ["The Cab"] },
+                "length": 203
+            },
+            "duration": 203,
+            "origin": "client:default"
+        },
+        { "time": 1669318400 },
+        {
+            "time": 1531205935,
+            "track": {
+                "artists": ["Lansdowne", "Someone Else"],
+                "title": "Burn Brighter",
+                "album": null,
+                "length": null
+            },
+            "duration": null,
+            "origin": "import:lastfm"
+        }
+    ]
+}"#;
+
+#[test]
+fn test_parse() {
+    let expected = Listen {
+        time: 1_669_318_360,
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        artists: vec!["The Cab".to_owned()],
+        album: Some("Symphony Soldier".to_owned()),
+        duration: Some(203),
+    };
+
+    let list = parse(SAMPLE.as_bytes()).expect("Failed to parse backup");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+}
+
+#[test]
+fn test_multiple_artists() {
+    let list = parse(SAMPLE.as_bytes()).expect("Failed to parse backup");
+    let listen = &list.0[1];
+    assert_eq!(listen.artist_name(), "Lansdowne, Someone Else");
+    assert_eq!(listen.release_name(), None);
+    assert_eq!(
+        serde_json::to_value(listen.track_metadata()).expect("Failed to serialize info"),
+        serde_json::json!({ "artist_names": ["Lansdowne", "Someone Else"] })
+    );
+}
+
+#[test]
+fn test_de_album_title() {
+    let listen: Listen = serde_json::from_str(r#"{"time": 1, "track": {"artists": ["A"], "title": "T", "album": "Album"}}"#).expect("Failed to parse scrobble");
+    assert_eq!(listen.release_name(), Some("Album"));
+}
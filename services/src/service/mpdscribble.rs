@@ -0,0 +1,103 @@
+use std::io::Read;
+
+use anyhow::Result;
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::Deserialize;
+use time::{
+    format_description::well_known::Rfc3339,
+    OffsetDateTime,
+};
+
+use super::MbidInfo;
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+
+/// Represents a single unsent scrobble from an mpdscribble journal
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    pub artist: String,
+
+    pub track: String,
+
+    #[release]
+    pub album: Option<String>,
+
+    pub mbid: Option<String>,
+
+    time: i64,
+
+    /// Track length in seconds
+    pub length: Option<u32>,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = MbidInfo<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> { Some(MbidInfo::new(self.mbid.as_deref(), None, None)) }
+}
+
+impl Listen {
+    /// Build a listen from the `key = value` lines of a single journal block
+    fn from_block(block: &str) -> Option<Self> {
+        let (mut artist, mut track, mut album, mut mbid, mut time, mut length) = (None, None, None, None, None, None);
+        for (key, value) in block.lines().filter_map(|l| l.split_once('=')) {
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match key.trim() {
+                "a" => artist = Some(value.to_owned()),
+                "t" => track = Some(value.to_owned()),
+                "b" => album = Some(value.to_owned()),
+                "m" => mbid = Some(value.to_owned()),
+                "i" => time = parse_time(value),
+                "l" => length = value.parse().ok(),
+                _ => {},
+            }
+        }
+
+        Some(Self {
+            artist: artist?,
+            track: track?,
+            album,
+            mbid,
+            time: time?,
+            length,
+        })
+    }
+}
+
+
+/// Parse an mpdscribble journal. Blocks missing an artist, title, or time are skipped.
+pub fn parse(mut source: impl Read) -> Result<ListenVec> {
+    let mut journal = String::new();
+    source.read_to_string(&mut journal)?;
+    Ok(journal.replace("\r\n", "\n").split("\n\n").filter_map(Listen::from_block).collect())
+}
+
+/// Newer versions write an ISO 8601 time in UTC, while older ones wrote a unix timestamp
+fn parse_time(value: &str) -> Option<i64> {
+    value
+        .parse()
+        .ok()
+        .or_else(|| OffsetDateTime::parse(value, &Rfc3339).ok().map(OffsetDateTime::unix_timestamp))
+}
+
+#[cfg(test)]
+mod tests;
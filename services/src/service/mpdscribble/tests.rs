@@ -0,0 +1,53 @@
+use super::*;
+
+const SAMPLE: &str = "a = The Cab
+t = Angel With A Shotgun
+b = Symphony Soldier
+m = b92334c4-574a-46f5-89d8-417fcd1e873f
+i = 2022-11-24T19:32:40Z
+l = 203
+n = 3
+o = P
+
+a = Lansdowne
+t = Burn Brighter
+b = 
+m = 
+i = 1531205935
+l = 220
+o = P
+
+a = Missing Time
+t = Broken
+l = 100
+
+";
+
+#[test]
+fn test_parse() {
+    let expected = Listen {
+        artist: "The Cab".to_owned(),
+        track: "Angel With A Shotgun".to_owned(),
+        album: Some("Symphony Soldier".to_owned()),
+        mbid: Some("b92334c4-574a-46f5-89d8-417fcd1e873f".to_owned()),
+        time: 1_669_318_360,
+        length: Some(203),
+    };
+
+    let list = parse(SAMPLE.as_bytes()).expect("Failed to parse journal");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+    assert_eq!(list.0[1].album, None);
+    assert_eq!(list.0[1].mbid, None);
+    assert_eq!(list.0[1].listened_at(), 1_531_205_935);
+}
+
+#[test]
+fn test_ser_mbid() {
+    let list = parse(SAMPLE.as_bytes()).expect("Failed to parse journal");
+    assert_eq!(
+        serde_json::to_value(list.0[0].track_metadata()).expect("Failed to serialize info"),
+        serde_json::json!({ "recording_mbid": "b92334c4-574a-46f5-89d8-417fcd1e873f" })
+    );
+    assert_eq!(serde_json::to_value(list.0[1].track_metadata()).expect("Failed to serialize info"), serde_json::json!({}));
+}
@@ -0,0 +1,228 @@
+use std::io::Read;
+
+use anyhow::Result;
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use scraper::{
+    node::Node,
+    ElementRef,
+    Html,
+    Selector,
+};
+use serde::{
+    ser::SerializeStruct,
+    Deserialize,
+};
+use time::{
+    format_description::FormatItem,
+    macros::format_description,
+    OffsetDateTime,
+    PrimitiveDateTime,
+    UtcOffset,
+};
+
+use super::youtube_music::{
+    clean_artist,
+    clean_title,
+};
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+const PLAY_MUSIC_HEADER: &str = "Google Play Music";
+const YOUTUBE_MUSIC_HEADER: &str = "YouTube Music";
+
+
+/// Represents a single activity card from a google takeout `My Activity.html`
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    header: String,
+
+    time: OffsetDateTime,
+
+    pub track: String,
+    pub artist: String,
+
+    pub url: Option<String>,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = Info<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time.unix_timestamp() }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(Info {
+            music_service: if self.header == PLAY_MUSIC_HEADER {
+                "play.google.com"
+            } else {
+                "music.youtube.com"
+            },
+            origin_url: self.url.as_deref(),
+        })
+    }
+}
+
+pub struct Info<'i> {
+    music_service: &'static str,
+    origin_url: Option<&'i str>,
+}
+
+impl serde::Serialize for Info<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("additional_info", 2)?;
+        state.serialize_field("music_service", self.music_service)?;
+        if let Some(url) = self.origin_url {
+            state.serialize_field("origin_url", url)?;
+        } else {
+            state.skip_field("origin_url")?;
+        }
+        state.end()
+    }
+}
+
+impl Listen {
+    /// Build a listen from an `outer-cell` card. The content is a title, artist, and date separated by `<br>`
+    fn from_card(card: ElementRef, selectors: &Selectors, local_offset: UtcOffset) -> Option<Self> {
+        let header = card.select(&selectors.header).next()?.text().collect::<String>().trim().to_owned();
+        if header != PLAY_MUSIC_HEADER && header != YOUTUBE_MUSIC_HEADER {
+            return None;
+        }
+
+        let lines = content_lines(card.select(&selectors.content).next()?);
+        let [(title, url), (artist, _), .., (date, _)] = lines.as_slice() else {
+            return None;
+        };
+
+        Some(Self {
+            header,
+            time: parse_date(date, local_offset)?,
+            track: clean_title(title).to_owned(),
+            artist: clean_artist(artist).to_owned(),
+            url: url.clone(),
+        })
+    }
+}
+
+struct Selectors {
+    card: Selector,
+    header: Selector,
+    content: Selector,
+}
+
+impl Selectors {
+    fn new() -> Self {
+        let parse = |s| Selector::parse(s).expect("Invalid selector");
+        Self {
+            card: parse("div.outer-cell"),
+            header: parse(".header-cell p"),
+            content: parse(".content-cell"),
+        }
+    }
+}
+
+/// Split the text of a cell at each `<br>`, along with the first link in each line
+fn content_lines(cell: ElementRef) -> Vec<(String, Option<String>)> {
+    let mut lines = vec![(String::new(), None)];
+    for child in cell.children() {
+        let (text, url) = lines.last_mut().expect("Lines is never empty");
+        match child.value() {
+            Node::Text(t) => text.push_str(t),
+            Node::Element(e) if e.name() == "br" => lines.push((String::new(), None)),
+            Node::Element(e) => {
+                text.extend(ElementRef::wrap(child).into_iter().flat_map(|e| e.text()));
+                if e.name() == "a" && url.is_none() {
+                    *url = e.attr("href").map(str::to_owned);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|(text, url)| (text.replace(['\u{a0}', '\u{202f}'], " ").trim().to_owned(), url))
+        .filter(|(text, _)| !text.is_empty())
+        .collect()
+}
+
+/// Parse a date such as `Nov 24, 2022, 7:32:40 PM EST` or `24 Nov 2022, 19:32:40 GMT`.
+/// Unknown timezones are assumed to be `local_offset`
+fn parse_date(date: &str, local_offset: UtcOffset) -> Option<OffsetDateTime> {
+    const FMTS: &[&[FormatItem]] = &[
+        format_description!("[month repr:short] [day padding:none], [year], [hour repr:12 padding:none]:[minute]:[second] [period]"),
+        format_description!("[month repr:short] [day padding:none], [year], [hour padding:none]:[minute]:[second]"),
+        format_description!("[day padding:none] [month repr:short] [year], [hour padding:none]:[minute]:[second]"),
+        format_description!("[day padding:none] [month repr:short] [year], [hour repr:12 padding:none]:[minute]:[second] [period]"),
+    ];
+
+    let date = date.replace("Sept ", "Sep ");
+    let (date, offset) = match date.rsplit_once(' ') {
+        Some((d, tz)) if tz.starts_with(|c: char| c.is_ascii_alphabetic()) && !["AM", "PM"].contains(&tz) => (d, parse_tz(tz).unwrap_or(local_offset)),
+        _ => (date.as_str(), local_offset),
+    };
+    FMTS.iter()
+        .find_map(|fmt| PrimitiveDateTime::parse(date, fmt).ok())
+        .map(|dt| dt.assume_offset(offset))
+}
+
+/// Convert a timezone abbreviation, or an offset like `GMT+02:00`, into an offset
+fn parse_tz(tz: &str) -> Option<UtcOffset> {
+    const FMTS: &[&[FormatItem]] = &[
+        format_description!("[offset_hour padding:none]:[offset_minute]"),
+        format_description!("[offset_hour padding:none]"),
+    ];
+
+    let hours = match tz {
+        "UTC" | "GMT" | "WET" => 0,
+        "BST" | "CET" | "WEST" => 1,
+        "CEST" | "EET" => 2,
+        "EEST" | "MSK" => 3,
+        "IST" => return UtcOffset::from_hms(5, 30, 0).ok(),
+        "JST" | "KST" => 9,
+        "AEST" => 10,
+        "AEDT" => 11,
+        "EDT" => -4,
+        "EST" | "CDT" => -5,
+        "CST" | "MDT" => -6,
+        "MST" | "PDT" => -7,
+        "PST" | "AKDT" => -8,
+        "AKST" => -9,
+        "HST" => -10,
+        _ => {
+            let offset = tz.strip_prefix("GMT").or_else(|| tz.strip_prefix("UTC"))?;
+            return FMTS.iter().find_map(|fmt| UtcOffset::parse(offset, fmt).ok());
+        },
+    };
+    UtcOffset::from_hms(hours, 0, 0).ok()
+}
+
+
+/// Parse a `My Activity.html`, keeping only the google play music and youtube music cards
+///
+/// Dates use the timezone abbreviation google printed, or `local_offset` if it isn't recognized
+pub fn parse(mut source: impl Read, local_offset: UtcOffset) -> Result<ListenVec> {
+    let mut html = String::new();
+    source.read_to_string(&mut html)?;
+
+    let document = Html::parse_document(&html);
+    let selectors = Selectors::new();
+    Ok(document
+        .select(&selectors.card)
+        .filter_map(|card| Listen::from_card(card, &selectors, local_offset))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests;
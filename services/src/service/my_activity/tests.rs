@@ -0,0 +1,49 @@
+use time::macros::{
+    datetime,
+    offset,
+};
+
+use super::*;
+
+const SAMPLE: &str = r#"<html><head><style type="text/css"></style></head><body><div class="mdl-grid">
+<div class="outer-cell mdl-cell mdl-cell--12-col mdl-shadow--2dp"><div class="mdl-grid"><div class="header-cell mdl-cell mdl-cell--12-col"><p class="mdl-typography--title">YouTube Music<br></p></div><div class="content-cell mdl-cell mdl-cell--6-col mdl-typography--body-1">Watched&nbsp;<a href="https://music.youtube.com/watch?v=AAAAAAAAAAA">Angel With A Shotgun</a><br><a href="https://www.youtube.com/channel/BBBBBBBBBBBBBBBBBBBBBBBB">The Cab - Topic</a><br>Nov 24, 2022, 7:32:40&#8239;PM EST<br></div><div class="content-cell mdl-cell mdl-cell--6-col mdl-typography--body-1 mdl-typography--text-right"></div><div class="content-cell mdl-cell mdl-cell--12-col mdl-typography--caption"><b>Products:</b><br>&emsp;YouTube<br></div></div></div>
+<div class="outer-cell mdl-cell mdl-cell--12-col mdl-shadow--2dp"><div class="mdl-grid"><div class="header-cell mdl-cell mdl-cell--12-col"><p class="mdl-typography--title">Google Play Music<br></p></div><div class="content-cell mdl-cell mdl-cell--6-col mdl-typography--body-1">Listened to Burn Brighter<br>Lansdowne<br>10 Jul 2018, 06:58:55 GMT<br></div><div class="content-cell mdl-cell mdl-cell--6-col mdl-typography--body-1 mdl-typography--text-right"></div></div></div>
+<div class="outer-cell mdl-cell mdl-cell--12-col mdl-shadow--2dp"><div class="mdl-grid"><div class="header-cell mdl-cell mdl-cell--12-col"><p class="mdl-typography--title">YouTube<br></p></div><div class="content-cell mdl-cell mdl-cell--6-col mdl-typography--body-1">Watched&nbsp;<a href="https://www.youtube.com/watch?v=CCCCCCCCCCC">Some Video</a><br><a href="https://www.youtube.com/channel/DDDDDDDDDDDDDDDDDDDDDDDD">Some Channel</a><br>Nov 24, 2022, 7:40:00&#8239;PM EST<br></div></div></div>
+<div class="outer-cell mdl-cell mdl-cell--12-col mdl-shadow--2dp"><div class="mdl-grid"><div class="header-cell mdl-cell mdl-cell--12-col"><p class="mdl-typography--title">YouTube Music<br></p></div><div class="content-cell mdl-cell mdl-cell--6-col mdl-typography--body-1">Watched a video that has been removed<br>Nov 24, 2022, 7:45:00&#8239;PM EST<br></div></div></div>
+</div></body></html>"#;
+
+#[test]
+fn test_parse() {
+    let expected = Listen {
+        header: "YouTube Music".to_owned(),
+        time: datetime!(2022-11-24 19:32:40 -5),
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        url: Some("https://music.youtube.com/watch?v=AAAAAAAAAAA".to_owned()),
+    };
+
+    let list = parse(SAMPLE.as_bytes(), UtcOffset::UTC).expect("Failed to parse html");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+}
+
+#[test]
+fn test_parse_play_music() {
+    let list = parse(SAMPLE.as_bytes(), UtcOffset::UTC).expect("Failed to parse html");
+    assert_eq!(list.0[1].track_name(), "Burn Brighter");
+    assert_eq!(list.0[1].artist_name(), "Lansdowne");
+    assert_eq!(list.0[1].listened_at(), datetime!(2018-07-10 06:58:55 UTC).unix_timestamp());
+    assert_eq!(
+        serde_json::to_value(list.0[1].track_metadata()).expect("Failed to serialize info"),
+        serde_json::json!({ "music_service": "play.google.com" })
+    );
+}
+
+#[test]
+fn test_parse_date() {
+    assert_eq!(parse_date("Sept 3, 2021, 1:02:03 AM GMT+02:00", UtcOffset::UTC), Some(datetime!(2021-09-03 01:02:03 +2)));
+    assert_eq!(parse_date("3 Sept 2021, 13:02:03 CEST", UtcOffset::UTC), Some(datetime!(2021-09-03 13:02:03 +2)));
+    assert_eq!(parse_date("Sep 3, 2021, 1:02:03 PM", offset!(-3)), Some(datetime!(2021-09-03 13:02:03 -3)));
+    assert_eq!(parse_date("Sep 3, 2021, 1:02:03 PM XYZ", offset!(+1)), Some(datetime!(2021-09-03 13:02:03 +1)));
+    assert_eq!(parse_date("not a date", UtcOffset::UTC), None);
+}
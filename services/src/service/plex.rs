@@ -0,0 +1,94 @@
+use std::{
+    io::Read,
+    path::Path,
+};
+
+use anyhow::Result;
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use rusqlite::Connection;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+/// Older servers store `viewed_at` as text instead of a unix timestamp
+const QUERY: &str = "SELECT
+    CASE typeof(viewed_at) WHEN 'integer' THEN viewed_at ELSE CAST(strftime('%s', viewed_at) AS INTEGER) END,
+    account_id, title, grandparent_title, parent_title
+FROM metadata_item_views
+WHERE metadata_type = 10";
+
+
+/// Represents a single track view from a plex media server library database
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    viewed_at: i64,
+
+    pub account_id: i64,
+
+    #[serde(rename = "title")]
+    pub track: String,
+
+    #[serde(rename = "grandparent_title")]
+    pub artist: String,
+
+    #[release]
+    #[serde(rename = "parent_title")]
+    pub album: Option<String>,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = Info;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.viewed_at }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> { Some(Info { media_player: "Plex" }) }
+}
+
+#[derive(Serialize)]
+pub struct Info {
+    media_player: &'static str,
+}
+
+
+/// Read all track views from a `com.plexapp.plugins.library.db`. Rows that fail to parse are skipped.
+///
+/// Only the main database file can be read from `source`, so views still in its `-wal` file are missed,
+/// use [`parse_file`] to include those
+pub fn parse(source: impl Read) -> Result<ListenVec> { read(&super::open_sqlite(source)?) }
+
+/// Same as [`parse`], but reads the database in place, including any views still in its `-wal` file
+pub fn parse_file(path: &Path) -> Result<ListenVec> { read(&super::open_sqlite_file(path)?) }
+
+fn read(conn: &Connection) -> Result<ListenVec> {
+    let mut stmt = conn.prepare(QUERY)?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Listen {
+            viewed_at: row.get(0)?,
+            account_id: row.get(1)?,
+            track: row.get(2)?,
+            artist: row.get(3)?,
+            album: row.get::<_, Option<String>>(4)?.filter(|a| !a.is_empty()),
+        })
+    })?;
+
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+#[cfg(test)]
+mod tests;
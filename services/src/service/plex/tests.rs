@@ -0,0 +1,71 @@
+use rusqlite::{
+    Connection,
+    MAIN_DB,
+};
+
+use super::*;
+
+fn sample_db() -> Vec<u8> {
+    let conn = Connection::open_in_memory().expect("Failed to open database");
+    conn.execute_batch(
+        "CREATE TABLE metadata_item_views (id INTEGER PRIMARY KEY, account_id INTEGER, guid TEXT, metadata_type INTEGER, library_section_id INTEGER, grandparent_title TEXT, parent_index INTEGER, parent_title TEXT, \"index\" INTEGER, title TEXT, thumb_url TEXT, viewed_at DATETIME, grandparent_guid TEXT, originally_available_at DATETIME, device_id INTEGER);
+        INSERT INTO metadata_item_views (account_id, metadata_type, grandparent_title, parent_title, title, viewed_at) VALUES
+            (1, 10, 'The Cab', 'Symphony Soldier', 'Angel With A Shotgun', 1669318360),
+            (1, 1, NULL, NULL, 'Some Movie', 1669318400),
+            (2, 10, 'Lansdowne', '', 'Burn Brighter', '2018-07-10 06:58:55'),
+            (2, 10, NULL, NULL, 'Unknown', 1669318500);",
+    )
+    .expect("Failed to create sample data");
+    conn.serialize(MAIN_DB).expect("Failed to serialize database").to_vec()
+}
+
+#[test]
+fn test_parse() {
+    let expected = Listen {
+        viewed_at: 1_669_318_360,
+        account_id: 1,
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        album: Some("Symphony Soldier".to_owned()),
+    };
+
+    let list = parse(sample_db().as_slice()).expect("Failed to read database");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+}
+
+#[test]
+fn test_parse_text_date() {
+    let list = parse(sample_db().as_slice()).expect("Failed to read database");
+    assert_eq!(list.0[1].account_id, 2);
+    assert_eq!(list.0[1].listened_at(), 1_531_205_935);
+    assert_eq!(list.0[1].release_name(), None);
+}
+
+#[test]
+fn test_parse_file_wal() {
+    let dir = std::env::temp_dir().join(format!("lb-importer-plex-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Failed to create directory");
+    let path = dir.join("com.plexapp.plugins.library.db");
+    std::fs::write(&path, sample_db()).expect("Failed to write database");
+
+    // Keep the writer open without checkpointing, the same as a running server
+    let writer = Connection::open(&path).expect("Failed to open database");
+    writer
+        .execute_batch(
+            "PRAGMA journal_mode=WAL; PRAGMA wal_autocheckpoint=0;
+            INSERT INTO metadata_item_views (account_id, metadata_type, grandparent_title, parent_title, title, viewed_at) VALUES
+                (1, 10, 'Lansdowne', 'Blue Collar Revolution', 'Blue Collar Revolution', 1672574400);",
+        )
+        .expect("Failed to write to database");
+
+    let list = parse_file(&path).expect("Failed to read database");
+    assert_eq!(list.0.len(), 3);
+    assert_eq!(list.0[2].track, "Blue Collar Revolution");
+
+    let copy = parse(std::fs::read(&path).expect("Failed to read file").as_slice()).expect("Failed to read database");
+    assert_eq!(copy.0.len(), 2);
+
+    drop(writer);
+    std::fs::remove_dir_all(dir).expect("Failed to clean up");
+}
@@ -0,0 +1,186 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::{
+        BufRead,
+        BufReader,
+        Read,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use anyhow::Result;
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use lofty::{
+    config::ParseOptions,
+    file::TaggedFileExt,
+    probe::Probe,
+    tag::{
+        Accessor,
+        ItemKey,
+    },
+};
+use serde::Deserialize;
+use time::UtcOffset;
+
+use super::MbidInfo;
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+
+/// Represents a single line from a rockbox `playback.log`, with tags read from the played file
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    timestamp: i64,
+
+    pub elapsed_ms: u64,
+    pub length_ms: u64,
+
+    /// Path of the file on the device
+    pub path: String,
+
+    pub track: String,
+    pub artist: String,
+
+    #[release]
+    pub album: Option<String>,
+
+    pub recording_mbid: Option<String>,
+    pub release_mbid: Option<String>,
+    pub artist_mbids: Vec<String>,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = MbidInfo<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.timestamp }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(MbidInfo::new(self.recording_mbid.as_deref(), self.release_mbid.as_deref(), self.artist_mbids.iter().map(String::as_str)))
+    }
+}
+
+impl Listen {
+    /// Fraction of the track that was played
+    pub fn played_ratio(&self) -> f64 {
+        if self.length_ms == 0 {
+            return 0.0;
+        }
+        self.elapsed_ms as f64 / self.length_ms as f64
+    }
+}
+
+
+/// Tags read from a file in the music library
+#[derive(Clone)]
+struct Tags {
+    title: String,
+    artist: String,
+    album: Option<String>,
+    recording_mbid: Option<String>,
+    release_mbid: Option<String>,
+    artist_mbids: Vec<String>,
+}
+
+impl Tags {
+    fn read(path: &Path) -> Option<Self> {
+        let file = Probe::open(path).ok()?.options(ParseOptions::new().read_properties(false)).read().ok()?;
+        let tag = file.primary_tag().or_else(|| file.first_tag())?;
+        let string = |key| tag.get_string(key).map(str::trim).filter(|s| !s.is_empty()).map(str::to_owned);
+
+        Some(Self {
+            title: tag.title().map(Cow::into_owned).filter(|t| !t.is_empty())?,
+            artist: tag.artist().map(Cow::into_owned).filter(|a| !a.is_empty())?,
+            album: tag.album().map(Cow::into_owned).filter(|a| !a.is_empty()),
+            recording_mbid: string(ItemKey::MusicBrainzRecordingId),
+            release_mbid: string(ItemKey::MusicBrainzReleaseId),
+            artist_mbids: tag
+                .get_strings(ItemKey::MusicBrainzArtistId)
+                .flat_map(|ids| ids.split(['/', ';']))
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        })
+    }
+}
+
+/// Find a file from the device in `library`, dropping leading directories from the device path until one exists.
+/// This allows the library to be the root of the device or any directory within it
+fn resolve(library: &Path, device_path: &str) -> Option<PathBuf> {
+    let parts: Vec<_> = device_path.split('/').filter(|p| !p.is_empty()).collect();
+    (0..parts.len())
+        .map(|i| parts[i..].iter().fold(library.to_path_buf(), |path, p| path.join(p)))
+        .find(|path| path.is_file())
+}
+
+
+/// Parse a rockbox `playback.log`, reading the tags of each played file from `library`.
+/// Lines that fail to parse, or files that can't be found or have no title and artist, are skipped.
+///
+/// Rockbox records timestamps in the local time of the device, so `local_offset` is used to convert those back to UTC
+pub fn parse(source: impl Read, library: &Path, local_offset: UtcOffset) -> Result<ListenVec> {
+    let offset = i64::from(local_offset.whole_seconds());
+    let mut tags = HashMap::new();
+    let mut listens = Vec::new();
+    for line in BufReader::new(source).lines() {
+        let line = line?;
+        if line.starts_with('#') {
+            continue;
+        }
+        // timestamp:elapsed_ms:length_ms:path
+        let mut fields = line.trim_end().splitn(4, ':');
+        let (Some(Ok(timestamp)), Some(Ok(elapsed_ms)), Some(Ok(length_ms)), Some(path)) =
+            (fields.next().map(str::parse::<i64>), fields.next().map(str::parse), fields.next().map(str::parse), fields.next())
+        else {
+            continue;
+        };
+        let Some(Tags {
+            title,
+            artist,
+            album,
+            recording_mbid,
+            release_mbid,
+            artist_mbids,
+        }) = tags
+            .entry(path.to_owned())
+            .or_insert_with(|| resolve(library, path).and_then(|p| Tags::read(&p)))
+            .clone()
+        else {
+            continue;
+        };
+
+        listens.push(Listen {
+            timestamp: timestamp - offset,
+            elapsed_ms,
+            length_ms,
+            path: path.to_owned(),
+            track: title,
+            artist,
+            album,
+            recording_mbid,
+            release_mbid,
+            artist_mbids,
+        });
+    }
+    Ok(listens.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,90 @@
+use std::fs;
+
+use time::macros::offset;
+
+use super::*;
+
+const SAMPLE: &str = "# Version: 1
+# Timestamp:Elapsed(ms):Length(ms):Path
+1669318360:200000:203000:/<microSD1>/Music/The Cab/angel.flac
+1669318600:15000:203000:/<microSD1>/Music/The Cab/angel.flac
+1669318800:100000:220000:/<microSD1>/Music/Lansdowne/missing.flac
+broken line
+";
+
+/// Build a flac file containing only the required stream info and a vorbis comment block
+fn flac(comments: &[&str]) -> Vec<u8> {
+    let mut data = b"fLaC".to_vec();
+    data.extend([0, 0, 0, 34]);
+    data.extend(4096u16.to_be_bytes());
+    data.extend(4096u16.to_be_bytes());
+    data.extend([0; 6]);
+    data.extend((44100u64 << 44 | 1 << 41 | 15 << 36).to_be_bytes());
+    data.extend([0; 16]);
+
+    let mut block = Vec::new();
+    block.extend(0u32.to_le_bytes());
+    block.extend((comments.len() as u32).to_le_bytes());
+    for c in comments {
+        block.extend((c.len() as u32).to_le_bytes());
+        block.extend(c.as_bytes());
+    }
+    data.push(0x80 | 4);
+    data.extend(&(block.len() as u32).to_be_bytes()[1..]);
+    data.extend(block);
+    data
+}
+
+fn library(name: &str) -> PathBuf {
+    let library = std::env::temp_dir().join(format!("lb-importer-rockbox-{name}-{}", std::process::id()));
+    let dir = library.join("Music/The Cab");
+    fs::create_dir_all(&dir).expect("Failed to create library");
+    fs::write(
+        dir.join("angel.flac"),
+        flac(&[
+            "TITLE=Angel With A Shotgun",
+            "ARTIST=The Cab",
+            "ALBUM=Symphony Soldier",
+            "MUSICBRAINZ_TRACKID=b92334c4-574a-46f5-89d8-417fcd1e873f",
+            "MUSICBRAINZ_ARTISTID=2ae9e8a4-6b10-4e88-a5a1-f44e4ec1ea03",
+        ]),
+    )
+    .expect("Failed to write track");
+    library
+}
+
+#[test]
+fn test_parse() {
+    let expected = Listen {
+        timestamp: 1_669_318_360 - 2 * 60 * 60,
+        elapsed_ms: 200_000,
+        length_ms: 203_000,
+        path: "/<microSD1>/Music/The Cab/angel.flac".to_owned(),
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        album: Some("Symphony Soldier".to_owned()),
+        recording_mbid: Some("b92334c4-574a-46f5-89d8-417fcd1e873f".to_owned()),
+        release_mbid: None,
+        artist_mbids: vec!["2ae9e8a4-6b10-4e88-a5a1-f44e4ec1ea03".to_owned()],
+    };
+
+    let library = library("parse");
+    let list = parse(SAMPLE.as_bytes(), &library, offset!(+2)).expect("Failed to parse log");
+    fs::remove_dir_all(library).ok();
+
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+    assert!(list.0[1].played_ratio() < 0.1);
+}
+
+#[test]
+fn test_resolve() {
+    let library = library("resolve");
+    let music = library.join("Music");
+    let expected = music.join("The Cab").join("angel.flac");
+
+    assert_eq!(resolve(&library, "/<microSD1>/Music/The Cab/angel.flac"), Some(expected.clone()));
+    assert_eq!(resolve(&music, "/<microSD1>/Music/The Cab/angel.flac"), Some(expected));
+    assert_eq!(resolve(&library, "/<microSD1>/Music/Lansdowne/missing.flac"), None);
+    fs::remove_dir_all(library).ok();
+}
@@ -0,0 +1,107 @@
+use std::io::{
+    BufRead,
+    BufReader,
+    Read,
+};
+
+use anyhow::{
+    bail,
+    Result,
+};
+use csv::ReaderBuilder;
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::Deserialize;
+use time::UtcOffset;
+
+use super::MbidInfo;
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+const HEADER: &str = "#AUDIOSCROBBLER/";
+const TZ_UTC: &str = "#TZ/UTC";
+const RATING_SKIPPED: &str = "S";
+
+
+/// Represents a single line from an audioscrobbler `.scrobbler.log`
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    pub artist: String,
+
+    #[release]
+    pub album: Option<String>,
+
+    pub track: String,
+
+    pub track_number: Option<u32>,
+
+    /// Track length in seconds
+    pub length: u32,
+
+    /// `L` if the track was listened to, or `S` if it was skipped
+    pub rating: String,
+
+    timestamp: i64,
+
+    #[serde(default)]
+    pub mbid: Option<String>,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = MbidInfo<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.timestamp }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> { Some(MbidInfo::new(self.mbid.as_deref(), None, None)) }
+}
+
+
+/// Parse a `.scrobbler.log`, skipping any tracks that were rated as skipped
+///
+/// Logs with an unknown timezone record timestamps in the local time of the device,
+/// so `local_offset` is used to convert those back to UTC
+pub fn parse(source: impl Read, local_offset: UtcOffset) -> Result<ListenVec> {
+    let mut source = BufReader::new(source);
+    let mut line = String::new();
+    let (mut header, mut utc) = (false, false);
+    while source.fill_buf()?.first() == Some(&b'#') {
+        line.clear();
+        source.read_line(&mut line)?;
+        header |= line.starts_with(HEADER);
+        utc |= line.trim_end() == TZ_UTC;
+    }
+    if !header {
+        bail!("Missing {HEADER} header");
+    }
+
+    let offset = if utc { 0 } else { i64::from(local_offset.whole_seconds()) };
+    Ok(ReaderBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(false)
+        .flexible(true)
+        .quoting(false)
+        .from_reader(source)
+        .into_deserialize::<Listen>()
+        .filter_map(Result::ok)
+        .filter(|l| l.rating != RATING_SKIPPED)
+        .map(|l| Listen {
+            timestamp: l.timestamp - offset,
+            ..l
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,50 @@
+use time::macros::offset;
+
+use super::*;
+
+const UTC_SAMPLE: &str = "#AUDIOSCROBBLER/1.1
+#TZ/UTC
+#CLIENT/Rockbox sansae200 $Revision$
+The Cab\tSymphony Soldier\tAngel With A Shotgun\t3\t203\tL\t1669318360\tb92334c4-574a-46f5-89d8-417fcd1e873f
+Lansdowne\t\tBurn Brighter\t\t220\tS\t1531205935\t
+Lansdowne\t\tBurn Brighter\t\t220\tL\t1531206200
+broken line
+";
+
+const UNKNOWN_SAMPLE: &str = "#AUDIOSCROBBLER/1.1
+#TZ/UNKNOWN
+#CLIENT/Rockbox sansae200 $Revision$
+The Cab\tSymphony Soldier\tAngel With A Shotgun\t3\t203\tL\t1669318360\t
+";
+
+#[test]
+fn test_parse_utc() {
+    let expected = Listen {
+        artist: "The Cab".to_owned(),
+        album: Some("Symphony Soldier".to_owned()),
+        track: "Angel With A Shotgun".to_owned(),
+        track_number: Some(3),
+        length: 203,
+        rating: "L".to_owned(),
+        timestamp: 1_669_318_360,
+        mbid: Some("b92334c4-574a-46f5-89d8-417fcd1e873f".to_owned()),
+    };
+
+    let list = parse(UTC_SAMPLE.as_bytes(), offset!(+2)).expect("Failed to parse log");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+    assert_eq!(list.0[1].album, None);
+    assert_eq!(list.0[1].mbid, None);
+    assert_eq!(list.0[1].listened_at(), 1_531_206_200);
+}
+
+#[test]
+fn test_parse_local() {
+    let list = parse(UNKNOWN_SAMPLE.as_bytes(), offset!(+2)).expect("Failed to parse log");
+    assert_eq!(list.0.len(), 1);
+    assert_eq!(list.0[0].listened_at(), 1_669_318_360 - 2 * 60 * 60);
+}
+
+#[test]
+#[should_panic(expected = "Missing")]
+fn test_parse_no_header() { parse("The Cab\tSymphony Soldier\tAngel With A Shotgun\t3\t203\tL\t1669318360\n".as_bytes(), UtcOffset::UTC).unwrap(); }
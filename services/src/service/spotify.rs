@@ -1,5 +1,10 @@
-use std::borrow::Borrow;
+use std::{
+    borrow::Borrow,
+    io::Read,
+    path::Path,
+};
 
+use anyhow::Result;
 use lb_importer_core::ListenData;
 use lb_importer_derive::IntoPayload;
 use serde::{
@@ -18,34 +23,112 @@ use time::{
 
 pub type ListenVec = super::ListenVec<Listen>;
 
+/// Audiobook entries don't include the author, so this is used as the artist instead. It's the name of the
+/// MusicBrainz artist used for unknown artists
+pub const UNKNOWN_AUTHOR: &str = "[unknown]";
+
+/// Chapters of the same book played within this many seconds of each other, not counting the time spent playing
+/// them, are part of the same listening session
+const BOOK_SESSION_GAP: i64 = 30 * 60;
+
 
 /// Represents a single entry from a spotify history dump
 #[cfg_attr(test, derive(PartialEq, Eq))]
 #[derive(Debug, Deserialize, IntoPayload)]
+#[serde(try_from = "Entry")]
 pub struct Listen {
-    #[serde(alias = "endTime", alias = "ts", deserialize_with = "parse_datetime")]
     time: OffsetDateTime,
 
-    #[serde(alias = "offline_timestamp", default, deserialize_with = "parse_ms_to_sec")]
     offline_time: Option<i64>,
 
-    #[serde(alias = "trackName", alias = "master_metadata_track_name")]
+    /// Track, episode, or chapter name
     pub track: String,
 
-    #[serde(alias = "artistName", alias = "master_metadata_album_artist_name")]
+    /// Artist or show name, or [`UNKNOWN_AUTHOR`] for audiobooks
     pub artist: String,
 
+    /// Album, or audiobook name
     #[release]
-    #[serde(alias = "master_metadata_album_album_name")]
     pub album: Option<String>,
 
     pub reason_start: Option<String>,
     pub reason_end: Option<String>,
 
-    pub spotify_track_uri: Option<String>,
+    /// Uri of the track, episode, or chapter
+    pub spotify_uri: Option<String>,
+
+    /// Uri of the book a chapter belongs to
+    pub audiobook_uri: Option<String>,
 
-    #[serde(alias = "msPlayed", alias = "ms_played")]
     pub ms_played: u32,
+
+    pub kind: Kind,
+}
+
+/// The type of content that was played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Track,
+    Episode,
+    Audiobook,
+}
+
+/// The kind of history stored in a file, based on its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// `Streaming_History_Audio_*`, `endsong_*`, `StreamingHistory_music_*`, and legacy `StreamingHistory*` files
+    Audio,
+    /// `Streaming_History_Video_*`, which is never imported
+    Video,
+    /// `StreamingHistory_podcast_*`, which only contains podcast episodes
+    Podcast,
+}
+
+impl FileKind {
+    pub fn from_path(path: &Path) -> Self {
+        match path.file_name().and_then(|n| n.to_str()) {
+            Some(n) if n.starts_with("Streaming_History_Video_") => Self::Video,
+            Some(n) if n.starts_with("StreamingHistory_podcast_") => Self::Podcast,
+            _ => Self::Audio,
+        }
+    }
+}
+
+impl Listen {
+    /// Convert an audiobook chapter into a listen of the whole book
+    pub fn into_audiobook(self) -> Self {
+        if self.kind != Kind::Audiobook {
+            return self;
+        }
+        Self {
+            track: self.album.clone().unwrap_or_else(|| self.track.clone()),
+            spotify_uri: self.audiobook_uri.clone(),
+            ..self
+        }
+    }
+}
+
+/// Replace the audiobook chapters in time sorted `listens` with a single listen of the book for each listening
+/// session, at the first chapter played. A session ends when anything else is played, or after a break of more than
+/// [`BOOK_SESSION_GAP`] seconds
+pub fn book_sessions(listens: Vec<Listen>) -> Vec<Listen> {
+    let mut session: Option<(Option<String>, i64, u32)> = None;
+    listens
+        .into_iter()
+        .filter(|l| {
+            if l.kind != Kind::Audiobook {
+                session = None;
+                return true;
+            }
+            let book = l.audiobook_uri.clone().or_else(|| l.album.clone());
+            let continued = session
+                .as_ref()
+                .is_some_and(|(prev, at, ms_played)| *prev == book && l.listened_at() - at <= i64::from((ms_played + l.ms_played) / 1000) + BOOK_SESSION_GAP);
+            session = Some((book, l.listened_at(), l.ms_played));
+            !continued
+        })
+        .map(Listen::into_audiobook)
+        .collect()
 }
 
 impl ListenData for Listen {
@@ -66,17 +149,19 @@ impl ListenData for Listen {
     #[inline]
     fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
         Some(Info {
-            spotify_id: self
-                .spotify_track_uri
+            kind: self.kind,
+            url: self
+                .spotify_uri
                 .as_ref()
-                .and_then(|uri| uri.rsplit_once(':'))
-                .map(|(_, id)| format!("https://open.spotify.com/track/{id}")),
+                .and_then(|uri| uri.strip_prefix("spotify:")?.split_once(':'))
+                .map(|(path, id)| format!("https://open.spotify.com/{path}/{id}")),
         })
     }
 }
 
 pub struct Info {
-    spotify_id: Option<String>,
+    kind: Kind,
+    url: Option<String>,
 }
 
 impl serde::Serialize for Info {
@@ -86,11 +171,13 @@ impl serde::Serialize for Info {
     {
         let mut state = serializer.serialize_struct("additional_info", 3)?;
         state.serialize_field("music_service", "spotify.com")?;
-        if let Some(ref id) = self.spotify_id {
-            state.serialize_field("spotify_id", id.as_str())?;
-            state.serialize_field("origin_url", id.as_str())?;
+        match self.url {
+            Some(ref url) if self.kind == Kind::Track => state.serialize_field("spotify_id", url.as_str())?,
+            _ => state.skip_field("spotify_id")?,
+        }
+        if let Some(ref url) = self.url {
+            state.serialize_field("origin_url", url.as_str())?;
         } else {
-            state.skip_field("spotify_id")?;
             state.skip_field("origin_url")?;
         }
         state.end()
@@ -98,6 +185,111 @@ impl serde::Serialize for Info {
 }
 
 
+/// Raw history entry, which may be a track, a podcast episode, or an audiobook chapter
+#[derive(Deserialize)]
+struct Entry {
+    #[serde(alias = "endTime", alias = "ts", deserialize_with = "parse_datetime")]
+    time: OffsetDateTime,
+
+    #[serde(alias = "offline_timestamp", default, deserialize_with = "parse_ms_to_sec")]
+    offline_time: Option<i64>,
+
+    #[serde(alias = "trackName", alias = "master_metadata_track_name")]
+    track: Option<String>,
+
+    #[serde(alias = "artistName", alias = "master_metadata_album_artist_name")]
+    artist: Option<String>,
+
+    #[serde(alias = "master_metadata_album_album_name")]
+    album: Option<String>,
+
+    spotify_track_uri: Option<String>,
+
+    #[serde(alias = "episodeName")]
+    episode_name: Option<String>,
+
+    #[serde(alias = "podcastName")]
+    episode_show_name: Option<String>,
+
+    spotify_episode_uri: Option<String>,
+
+    audiobook_title: Option<String>,
+    audiobook_uri: Option<String>,
+    audiobook_chapter_title: Option<String>,
+    audiobook_chapter_uri: Option<String>,
+
+    reason_start: Option<String>,
+    reason_end: Option<String>,
+
+    #[serde(alias = "msPlayed", alias = "ms_played")]
+    ms_played: u32,
+}
+
+impl TryFrom<Entry> for Listen {
+    type Error = &'static str;
+
+    fn try_from(entry: Entry) -> Result<Self, Self::Error> {
+        let (kind, track, artist, album, spotify_uri, audiobook_uri) = match entry {
+            Entry {
+                track: Some(track),
+                artist: Some(artist),
+                album,
+                spotify_track_uri,
+                ..
+            } => (Kind::Track, track, artist, album, spotify_track_uri, None),
+            Entry {
+                episode_name: Some(episode),
+                episode_show_name: Some(show),
+                spotify_episode_uri,
+                ..
+            } => (Kind::Episode, episode, show, None, spotify_episode_uri, None),
+            Entry {
+                audiobook_title: Some(book),
+                audiobook_uri,
+                audiobook_chapter_title,
+                audiobook_chapter_uri,
+                ..
+            } => (
+                Kind::Audiobook,
+                audiobook_chapter_title.unwrap_or_else(|| book.clone()),
+                UNKNOWN_AUTHOR.to_owned(),
+                Some(book),
+                audiobook_chapter_uri,
+                audiobook_uri,
+            ),
+            _ => return Err("entry is missing track, episode, and audiobook details"),
+        };
+
+        Ok(Self {
+            time: entry.time,
+            offline_time: entry.offline_time,
+            track,
+            artist,
+            album,
+            reason_start: entry.reason_start,
+            reason_end: entry.reason_end,
+            spotify_uri,
+            audiobook_uri,
+            ms_played: entry.ms_played,
+            kind,
+        })
+    }
+}
+
+
+/// Read all entries from a history file of the given kind. Entries that fail to parse are skipped.
+pub fn parse(source: impl Read, kind: FileKind) -> Result<ListenVec> {
+    match kind {
+        FileKind::Audio => Ok(serde_json::from_reader(source)?),
+        FileKind::Video => Ok(ListenVec::from_iter([])),
+        FileKind::Podcast => Ok(serde_json::from_reader::<_, ListenVec>(source)?
+            .into_iter()
+            .filter(|l| l.kind == Kind::Episode)
+            .collect()),
+    }
+}
+
+
 fn parse_datetime<'de, D>(de: D) -> Result<OffsetDateTime, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -33,6 +33,38 @@ const SIMPLE_SAMPLE: &str = r#"{
     "msPlayed" : 60265
 }"#;
 
+const EPISODE_SAMPLE: &str = r#"{
+    "ts": "2022-11-24T19:32:40Z",
+    "ms_played": 1804000,
+    "master_metadata_track_name": null,
+    "master_metadata_album_artist_name": null,
+    "master_metadata_album_album_name": null,
+    "spotify_track_uri": null,
+    "episode_name": "Episode 1",
+    "episode_show_name": "Some Show",
+    "spotify_episode_uri": "spotify:episode:5Xt5DXGzch68nYYamXrNxZ",
+    "reason_start": "clickrow",
+    "reason_end": "endplay"
+}"#;
+
+const AUDIOBOOK_SAMPLE: &str = r#"{
+    "ts": "2023-10-01T20:15:00Z",
+    "ms_played": 912000,
+    "master_metadata_track_name": null,
+    "master_metadata_album_artist_name": null,
+    "master_metadata_album_album_name": null,
+    "spotify_track_uri": null,
+    "episode_name": null,
+    "episode_show_name": null,
+    "spotify_episode_uri": null,
+    "audiobook_title": "Some Book",
+    "audiobook_uri": "spotify:show:4kYCRYJ3yK5DQbP5tbfZby",
+    "audiobook_chapter_uri": "spotify:episode:3jD6Pc6MRGj3rWFXNzj5aD",
+    "audiobook_chapter_title": "Chapter 1",
+    "reason_start": "clickrow",
+    "reason_end": "endplay"
+}"#;
+
 macro_rules! LIST_SAMPLE {
     () => {
         format!("[{0},{{}},{0},{1},{{}},{1}]", SIMPLE_SAMPLE, FULL_SAMPLE)
@@ -52,10 +84,12 @@ fn test_de_simple() {
         track: EXPECTED_TRACK.to_owned(),
         artist: EXPECTED_ARTIST.to_owned(),
         album: None,
-        spotify_track_uri: None,
+        spotify_uri: None,
+        audiobook_uri: None,
         ms_played: EXPECTED_MS_PLAYED,
         reason_start: None,
         reason_end: None,
+        kind: Kind::Track,
     };
 
     let simple: Listen = serde_json::from_str(SIMPLE_SAMPLE).expect("Failed to parse simple entry");
@@ -70,16 +104,99 @@ fn test_de_full() {
         track: EXPECTED_TRACK.to_owned(),
         artist: EXPECTED_ARTIST.to_owned(),
         album: EXPECTED_ALBUM.map(str::to_owned),
-        spotify_track_uri: Some("spotify:track:6BUMVGOnIeOIE6YetJGGDT".to_owned()),
+        spotify_uri: Some("spotify:track:6BUMVGOnIeOIE6YetJGGDT".to_owned()),
+        audiobook_uri: None,
         ms_played: EXPECTED_MS_PLAYED,
         reason_start: Some("trackdone".to_owned()),
         reason_end: Some("fwdbtn".to_owned()),
+        kind: Kind::Track,
     };
 
     let full: Listen = serde_json::from_str(FULL_SAMPLE).expect("Failed to parse full entry");
     assert_eq!(full, expected);
 }
 
+#[test]
+fn test_de_episode() {
+    let episode: Listen = serde_json::from_str(EPISODE_SAMPLE).expect("Failed to parse episode entry");
+    assert_eq!(episode.kind, Kind::Episode);
+    assert_eq!(episode.track_name(), "Episode 1");
+    assert_eq!(episode.artist_name(), "Some Show");
+    assert_eq!(episode.release_name(), None);
+    assert_eq!(
+        serde_json::to_value(episode.track_metadata()).expect("Failed to serialize info"),
+        serde_json::json!({
+            "music_service": "spotify.com",
+            "origin_url": "https://open.spotify.com/episode/5Xt5DXGzch68nYYamXrNxZ",
+        })
+    );
+}
+
+#[test]
+fn test_de_audiobook() {
+    let chapter: Listen = serde_json::from_str(AUDIOBOOK_SAMPLE).expect("Failed to parse audiobook entry");
+    assert_eq!(chapter.kind, Kind::Audiobook);
+    assert_eq!(chapter.track_name(), "Chapter 1");
+    assert_eq!(chapter.artist_name(), UNKNOWN_AUTHOR);
+    assert_eq!(chapter.release_name(), Some("Some Book"));
+
+    let book = chapter.into_audiobook();
+    assert_eq!(book.track_name(), "Some Book");
+    assert_eq!(book.artist_name(), UNKNOWN_AUTHOR);
+    assert_eq!(book.release_name(), Some("Some Book"));
+    assert_eq!(
+        serde_json::to_value(book.track_metadata()).expect("Failed to serialize info"),
+        serde_json::json!({
+            "music_service": "spotify.com",
+            "origin_url": "https://open.spotify.com/show/4kYCRYJ3yK5DQbP5tbfZby",
+        })
+    );
+}
+
+fn chapter(book: &str, chapter: &str, time: OffsetDateTime, ms_played: u32) -> Listen {
+    Listen {
+        time,
+        offline_time: None,
+        track: chapter.to_owned(),
+        artist: UNKNOWN_AUTHOR.to_owned(),
+        album: Some(book.to_owned()),
+        spotify_uri: None,
+        audiobook_uri: Some(format!("spotify:show:{book}")),
+        ms_played,
+        reason_start: None,
+        reason_end: None,
+        kind: Kind::Audiobook,
+    }
+}
+
+#[test]
+fn test_book_sessions() {
+    let track: Listen = serde_json::from_str(SIMPLE_SAMPLE).expect("Failed to parse simple entry");
+    let listens = vec![
+        chapter("Book A", "Chapter 1", datetime!(2023-10-01 20:00 UTC), 900_000),
+        chapter("Book A", "Chapter 2", datetime!(2023-10-01 20:15 UTC), 900_000),
+        // Same book after a long break
+        chapter("Book A", "Chapter 3", datetime!(2023-10-02 20:00 UTC), 900_000),
+        // Different book straight after
+        chapter("Book B", "Chapter 1", datetime!(2023-10-02 20:15 UTC), 900_000),
+        // Interrupted by a track
+        Listen {
+            time: datetime!(2023-10-02 20:30 UTC),
+            ..track
+        },
+        chapter("Book B", "Chapter 2", datetime!(2023-10-02 20:35 UTC), 900_000),
+    ];
+
+    let sessions: Vec<_> = book_sessions(listens).into_iter().map(|l| (l.listened_at(), l.track)).collect();
+    assert_eq!(sessions, [
+        (datetime!(2023-10-01 20:00 UTC).unix_timestamp(), "Book A".to_owned()),
+        (datetime!(2023-10-02 20:00 UTC).unix_timestamp(), "Book A".to_owned()),
+        (datetime!(2023-10-02 20:15 UTC).unix_timestamp(), "Book B".to_owned()),
+        (datetime!(2023-10-02 20:30 UTC).unix_timestamp(), EXPECTED_TRACK.to_owned()),
+        (datetime!(2023-10-02 20:35 UTC).unix_timestamp(), "Book B".to_owned()),
+    ]);
+}
+
 #[test]
 fn test_de_list_safe() {
     let list: ListenVec = serde_json::from_str(LIST_SAMPLE!().as_str()).expect("Failed to parse list");
@@ -89,3 +206,37 @@ fn test_de_list_safe() {
 #[test]
 #[should_panic(expected = "missing field")]
 fn test_de_list_fail() { serde_json::from_str::<Vec<Listen>>(LIST_SAMPLE!().as_str()).expect(""); }
+
+#[test]
+fn test_file_kind() {
+    for name in [
+        "Streaming_History_Audio_2019-2021_3.json",
+        "endsong_0.json",
+        "StreamingHistory0.json",
+        "StreamingHistory_music_0.json",
+    ] {
+        assert_eq!(FileKind::from_path(Path::new(name)), FileKind::Audio, "{name}");
+    }
+    assert_eq!(FileKind::from_path(Path::new("Streaming_History_Video_2019-2024.json")), FileKind::Video);
+    assert_eq!(FileKind::from_path(Path::new("StreamingHistory_podcast_0.json")), FileKind::Podcast);
+}
+
+#[test]
+fn test_parse_kind() {
+    let list = format!("[{FULL_SAMPLE},{EPISODE_SAMPLE}]");
+    assert_eq!(parse(list.as_bytes(), FileKind::Audio).expect("Failed to parse audio").0.len(), 2);
+    assert_eq!(parse(list.as_bytes(), FileKind::Video).expect("Failed to parse video").0.len(), 0);
+
+    let podcasts = parse(list.as_bytes(), FileKind::Podcast).expect("Failed to parse podcasts");
+    assert_eq!(podcasts.0.len(), 1);
+    assert_eq!(podcasts.0[0].kind, Kind::Episode);
+}
+
+#[test]
+fn test_de_legacy_podcast() {
+    let episode: Listen =
+        serde_json::from_str(r#"{"endTime": "2022-11-24 19:32", "podcastName": "Some Show", "episodeName": "Episode 1", "msPlayed": 1804000}"#)
+            .expect("Failed to parse legacy podcast entry");
+    assert_eq!(episode.kind, Kind::Episode);
+    assert_eq!(episode.artist_name(), "Some Show");
+}
@@ -0,0 +1,161 @@
+use std::io::{
+    BufReader,
+    Read,
+};
+
+use anyhow::Result;
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use time::{
+    Date,
+    Month,
+    OffsetDateTime,
+    Time,
+    UtcOffset,
+};
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+
+/// Represents a single played track from a traktor history playlist
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+#[serde(try_from = "Entry")]
+pub struct Listen {
+    time: OffsetDateTime,
+
+    pub track: String,
+    pub artist: String,
+
+    #[release]
+    pub album: Option<String>,
+
+    /// Seconds the track was playing for
+    pub duration: Option<u32>,
+
+    /// `false` if the track was only previewed on the headphones
+    pub played_public: bool,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = Info;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time.unix_timestamp() }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn release_name(&self) -> Option<&str> { self.album.as_deref() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> { Some(Info { media_player: "Traktor" }) }
+}
+
+#[derive(Serialize)]
+pub struct Info {
+    media_player: &'static str,
+}
+
+
+#[derive(Deserialize)]
+struct Nml {
+    #[serde(rename = "COLLECTION")]
+    collection: Collection,
+}
+
+#[derive(Deserialize)]
+struct Collection {
+    /// Deserialized directly, since attributes can't be parsed as numbers once buffered by `VecSkipError`
+    #[serde(rename = "ENTRY", default)]
+    entries: Vec<Entry>,
+}
+
+/// An `ENTRY` element from the collection
+#[derive(Deserialize)]
+struct Entry {
+    #[serde(rename = "@TITLE")]
+    title: Option<String>,
+    #[serde(rename = "@ARTIST")]
+    artist: Option<String>,
+    #[serde(rename = "ALBUM")]
+    album: Option<Album>,
+    #[serde(rename = "EXTENDEDDATA")]
+    extended_data: Option<ExtendedData>,
+}
+
+#[derive(Deserialize)]
+struct Album {
+    #[serde(rename = "@TITLE")]
+    title: Option<String>,
+}
+
+/// Only present on entries in a history playlist
+#[derive(Deserialize)]
+struct ExtendedData {
+    /// Packed as `year << 16 | month << 8 | day`
+    #[serde(rename = "@STARTDATE")]
+    start_date: u32,
+    /// Seconds since midnight
+    #[serde(rename = "@STARTTIME")]
+    start_time: u32,
+    #[serde(rename = "@DURATION")]
+    duration: Option<f64>,
+    #[serde(rename = "@PLAYEDPUBLIC")]
+    played_public: Option<u8>,
+}
+
+impl TryFrom<Entry> for Listen {
+    type Error = String;
+
+    fn try_from(entry: Entry) -> Result<Self, Self::Error> {
+        let data = entry.extended_data.ok_or("Not a history entry")?;
+        let date = Date::from_calendar_date(
+            (data.start_date >> 16) as i32,
+            Month::try_from((data.start_date >> 8 & 0xFF) as u8).map_err(|e| e.to_string())?,
+            (data.start_date & 0xFF) as u8,
+        )
+        .map_err(|e| e.to_string())?;
+        let time =
+            Time::from_hms((data.start_time / 3600) as u8, (data.start_time / 60 % 60) as u8, (data.start_time % 60) as u8).map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            time: date.with_time(time).assume_utc(),
+            track: entry.title.filter(|t| !t.is_empty()).ok_or("Missing title")?,
+            artist: entry.artist.filter(|a| !a.is_empty()).ok_or("Missing artist")?,
+            album: entry.album.and_then(|a| a.title).filter(|a| !a.is_empty()),
+            duration: data.duration.map(|d| d.round() as u32),
+            played_public: data.played_public != Some(0),
+        })
+    }
+}
+
+
+/// Parse a traktor history `.nml` file, skipping any tracks that were only previewed.
+///
+/// Traktor records the local time of the computer, so `local_offset` is used to convert those to UTC
+pub fn parse(source: impl Read, local_offset: UtcOffset) -> Result<ListenVec> {
+    let nml: Nml = quick_xml::de::from_reader(BufReader::new(source))?;
+    Ok(nml
+        .collection
+        .entries
+        .into_iter()
+        .filter_map(|e| Listen::try_from(e).ok())
+        .filter(|l| l.played_public)
+        .map(|l| Listen {
+            time: l.time.replace_offset(local_offset),
+            ..l
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests;
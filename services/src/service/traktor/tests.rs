@@ -0,0 +1,57 @@
+use time::macros::{
+    datetime,
+    offset,
+};
+
+use super::*;
+
+const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?>
+<NML VERSION="19"><HEAD COMPANY="www.native-instruments.com" PROGRAM="Traktor"></HEAD>
+<MUSICFOLDERS></MUSICFOLDERS>
+<COLLECTION ENTRIES="4">
+<ENTRY MODIFIED_DATE="2022/11/24" MODIFIED_TIME="70360" TITLE="Angel With A Shotgun" ARTIST="The Cab"><LOCATION DIR="/:Music/:" FILE="angel.mp3" VOLUME="C:" VOLUMEID=""></LOCATION>
+<ALBUM TRACK="3" TITLE="Symphony Soldier"></ALBUM><MODIFICATION_INFO AUTHOR_TYPE="user"></MODIFICATION_INFO>
+<INFO BITRATE="320000" PLAYTIME="203"></INFO>
+<EXTENDEDDATA DECK="0" DURATION="185.3" EXTENDEDTYPE="HistoryData" PLAYEDPUBLIC="1" STARTDATE="132516632" STARTTIME="70360"></EXTENDEDDATA>
+</ENTRY>
+<ENTRY TITLE="Burn Brighter" ARTIST="Lansdowne"><LOCATION DIR="/:Music/:" FILE="burn.mp3" VOLUME="C:" VOLUMEID=""></LOCATION>
+<EXTENDEDDATA DECK="1" DURATION="60.0" EXTENDEDTYPE="HistoryData" PLAYEDPUBLIC="1" STARTDATE="132253450" STARTTIME="25135"></EXTENDEDDATA>
+</ENTRY>
+<ENTRY TITLE="Previewed" ARTIST="Lansdowne">
+<EXTENDEDDATA DECK="1" DURATION="10.0" EXTENDEDTYPE="HistoryData" PLAYEDPUBLIC="0" STARTDATE="132253450" STARTTIME="25200"></EXTENDEDDATA>
+</ENTRY>
+<ENTRY TITLE="Not History" ARTIST="Lansdowne"></ENTRY>
+</COLLECTION>
+<PLAYLISTS></PLAYLISTS>
+</NML>
+"#;
+
+#[test]
+fn test_parse() {
+    let expected = Listen {
+        time: datetime!(2022-11-24 19:32:40 +2),
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        album: Some("Symphony Soldier".to_owned()),
+        duration: Some(185),
+        played_public: true,
+    };
+
+    let list = parse(SAMPLE.as_bytes(), offset!(+2)).expect("Failed to parse nml");
+    assert_eq!(list.0.len(), 2);
+    assert_eq!(list.0[0], expected);
+    assert_eq!(list.0[0].listened_at(), datetime!(2022-11-24 17:32:40 UTC).unix_timestamp());
+}
+
+#[test]
+fn test_parse_no_album() {
+    let list = parse(SAMPLE.as_bytes(), UtcOffset::UTC).expect("Failed to parse nml");
+    assert_eq!(list.0[1].time, datetime!(2018-07-10 06:58:55 UTC));
+    assert_eq!(list.0[1].release_name(), None);
+}
+
+#[test]
+fn test_parse_empty() {
+    let list = parse(r#"<NML VERSION="19"><COLLECTION ENTRIES="0"></COLLECTION></NML>"#.as_bytes(), UtcOffset::UTC).expect("Failed to parse nml");
+    assert!(list.0.is_empty());
+}
@@ -0,0 +1,116 @@
+use std::io::Read;
+
+use lb_importer_core::ListenData;
+use lb_importer_derive::IntoPayload;
+use serde::{
+    ser::SerializeStruct,
+    Deserialize,
+};
+use time::OffsetDateTime;
+
+pub type ListenVec = super::ListenVec<Listen>;
+
+const MUSIC_HEADER: &str = "YouTube Music";
+
+
+/// Represents a single entry from a google takeout youtube `watch-history.json`
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Debug, Deserialize, IntoPayload)]
+pub struct Listen {
+    header: String,
+
+    #[serde(with = "time::serde::rfc3339")]
+    time: OffsetDateTime,
+
+    #[serde(rename = "title", deserialize_with = "parse_title")]
+    pub track: String,
+
+    #[serde(rename = "subtitles", deserialize_with = "parse_subtitles")]
+    pub artist: String,
+
+    #[serde(rename = "titleUrl")]
+    pub url: Option<String>,
+}
+
+impl ListenData for Listen {
+    type MetaType<'m> = Info<'m>;
+
+    #[inline]
+    fn listened_at(&self) -> i64 { self.time.unix_timestamp() }
+
+    #[inline]
+    fn track_name(&self) -> &str { self.track.as_str() }
+
+    #[inline]
+    fn artist_name(&self) -> &str { self.artist.as_str() }
+
+    #[inline]
+    fn track_metadata(&self) -> Option<Self::MetaType<'_>> {
+        Some(Info {
+            origin_url: self.url.as_deref(),
+        })
+    }
+}
+
+pub struct Info<'i> {
+    origin_url: Option<&'i str>,
+}
+
+impl serde::Serialize for Info<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("additional_info", 2)?;
+        state.serialize_field("music_service", "music.youtube.com")?;
+        if let Some(url) = self.origin_url {
+            state.serialize_field("origin_url", url)?;
+        } else {
+            state.skip_field("origin_url")?;
+        }
+        state.end()
+    }
+}
+
+
+/// Remove the suffix youtube adds to the names of auto-generated artist channels
+pub(crate) fn clean_artist(name: &str) -> &str { name.strip_suffix(" - Topic").unwrap_or(name).trim() }
+
+/// Remove the action google adds before the title of an activity
+pub(crate) fn clean_title(title: &str) -> &str {
+    ["Watched ", "Listened to "]
+        .iter()
+        .find_map(|prefix| title.strip_prefix(prefix))
+        .unwrap_or(title)
+        .trim()
+}
+
+/// Parse a `watch-history.json`, keeping only the entries that were played through youtube music
+pub fn parse(source: impl Read) -> serde_json::Result<ListenVec> {
+    serde_json::from_reader::<_, ListenVec>(source).map(|history| history.into_iter().filter(|l| l.header == MUSIC_HEADER).collect())
+}
+
+fn parse_title<'de, D>(de: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    String::deserialize(de).map(|title| clean_title(&title).to_owned())
+}
+
+fn parse_subtitles<'de, D>(de: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Subtitle {
+        name: String,
+    }
+
+    Vec::<Subtitle>::deserialize(de)?
+        .first()
+        .map(|s| clean_artist(&s.name).to_owned())
+        .ok_or_else(|| serde::de::Error::invalid_length(0, &"at least one subtitle"))
+}
+
+#[cfg(test)]
+mod tests;
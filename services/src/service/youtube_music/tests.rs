@@ -0,0 +1,66 @@
+use time::macros::datetime;
+
+use super::*;
+
+const SAMPLE: &str = r#"{
+    "header": "YouTube Music",
+    "title": "Watched Angel With A Shotgun",
+    "titleUrl": "https://music.youtube.com/watch?v=abcdefghijk",
+    "subtitles": [{
+        "name": "The Cab - Topic",
+        "url": "https://www.youtube.com/channel/UC0000000000000000000000"
+    }],
+    "time": "2022-11-24T19:32:40.617Z",
+    "products": ["YouTube"],
+    "activityControls": ["YouTube watch history"]
+}"#;
+
+const VIDEO_SAMPLE: &str = r#"{
+    "header": "YouTube",
+    "title": "Watched Some Video",
+    "titleUrl": "https://www.youtube.com/watch?v=abcdefghijk",
+    "subtitles": [{ "name": "Some Channel" }],
+    "time": "2022-11-24T19:40:00Z",
+    "products": ["YouTube"]
+}"#;
+
+const REMOVED_SAMPLE: &str = r#"{
+    "header": "YouTube Music",
+    "title": "Watched a video that has been removed",
+    "time": "2022-11-24T19:45:00Z",
+    "products": ["YouTube"]
+}"#;
+
+#[test]
+fn test_de() {
+    let expected = Listen {
+        header: MUSIC_HEADER.to_owned(),
+        time: datetime!(2022-11-24 19:32:40.617 UTC),
+        track: "Angel With A Shotgun".to_owned(),
+        artist: "The Cab".to_owned(),
+        url: Some("https://music.youtube.com/watch?v=abcdefghijk".to_owned()),
+    };
+
+    let listen: Listen = serde_json::from_str(SAMPLE).expect("Failed to parse entry");
+    assert_eq!(listen, expected);
+}
+
+#[test]
+fn test_parse_music_only() {
+    let list = parse(format!("[{SAMPLE},{VIDEO_SAMPLE},{REMOVED_SAMPLE}]").as_bytes()).expect("Failed to parse history");
+    assert_eq!(list.0.len(), 1);
+    assert_eq!(list.0[0].artist_name(), "The Cab");
+}
+
+#[test]
+fn test_ser_info() {
+    let listen: Listen = serde_json::from_str(SAMPLE).expect("Failed to parse entry");
+    let info = serde_json::to_value(listen.track_metadata()).expect("Failed to serialize info");
+    assert_eq!(
+        info,
+        serde_json::json!({
+            "music_service": "music.youtube.com",
+            "origin_url": "https://music.youtube.com/watch?v=abcdefghijk",
+        })
+    );
+}